@@ -0,0 +1,60 @@
+//! Compares neighbor queries with the full Euclidean distance against `distance_within`, on 20-dimensional data.
+//!
+//! Run with `cargo bench --bench early_termination`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use d3st_wasm::fuzzy_dbscan::MetricSpace;
+use d3st_wasm::packing::{read_packed_data_dyn, DataPointDyn};
+
+const POINTS: usize = 2000;
+const DIMENSIONS: usize = 20;
+const EPS: f64 = 0.5;
+
+/// Uniform values in `0..1` from a fixed linear congruential generator.
+fn generate_data() -> Vec<u8> {
+    let mut state: u32 = 12345;
+    (0..POINTS * DIMENSIONS)
+        .flat_map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((state >> 8) as f32 / (1 << 24) as f32).to_ne_bytes()
+        })
+        .collect()
+}
+
+fn time_queries(points: &[DataPointDyn], within: bool) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut neighbors = 0;
+    for a in points {
+        for b in points {
+            let is_neighbor = if within {
+                a.distance_within(b, EPS).is_some()
+            } else {
+                a.distance(b) <= EPS
+            };
+            neighbors += black_box(is_neighbor) as usize;
+        }
+    }
+    (start.elapsed(), neighbors)
+}
+
+fn main() {
+    let data = generate_data();
+    let points = read_packed_data_dyn(&data, DIMENSIONS).unwrap();
+
+    let (full, full_neighbors) = time_queries(&points, false);
+    let (within, within_neighbors) = time_queries(&points, true);
+    assert_eq!(full_neighbors, within_neighbors);
+
+    println!(
+        "{} points with {} dimensions, eps = {}",
+        POINTS, DIMENSIONS, EPS
+    );
+    println!("full distance:   {:?}", full);
+    println!("distance_within: {:?}", within);
+    println!(
+        "speedup:         {:.2}x",
+        full.as_secs_f64() / within.as_secs_f64()
+    );
+}
@@ -0,0 +1,836 @@
+//! Summaries of clustering results.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::fuzzy_dbscan::{Assignment, Category, Cluster, MetricSpace};
+use crate::packing::PointScalar;
+
+/// Clusters with more members than this use a sample of members to find their [medoid].
+pub const MEDOID_SAMPLE_SIZE: usize = 512;
+
+/// Returns whether `cluster` is the group of noise points rather than a real cluster.
+pub fn is_noise(cluster: &Cluster) -> bool {
+    cluster
+        .iter()
+        .all(|assignment| assignment.category == Category::Noise)
+}
+
+/// Keeps the `max_clusters` largest real clusters and turns the points of the others into noise, unless they also
+/// belong to a kept cluster.
+///
+/// Kept clusters stay in their order, ties in size go to the earlier cluster, and the noise group stays last.
+pub fn keep_largest(clusters: Vec<Cluster>, max_clusters: usize) -> Vec<Cluster> {
+    let real = clusters.len() - clusters.last().map_or(0, |c| is_noise(c) as usize);
+    let mut keep = vec![real <= max_clusters; real];
+    if real > max_clusters {
+        let mut order: Vec<usize> = (0..real).collect();
+        order.sort_by(|&a, &b| clusters[b].len().cmp(&clusters[a].len()));
+        for &cluster in &order[..max_clusters] {
+            keep[cluster] = true;
+        }
+    }
+    dissolve(clusters, &keep)
+}
+
+/// Turns the real clusters whose mean label is below `min_mean_label` into noise like [`keep_largest`], e.g. to
+/// drop clusters of mostly uncertain border points.
+pub fn keep_confident(clusters: Vec<Cluster>, min_mean_label: f64) -> Vec<Cluster> {
+    let keep: Vec<bool> = clusters
+        .iter()
+        .filter(|cluster| !is_noise(cluster))
+        .map(|cluster| {
+            let sum: f64 = cluster.iter().map(|assignment| assignment.label).sum();
+            sum >= min_mean_label * cluster.len() as f64
+        })
+        .collect();
+    if keep.iter().all(|&keep| keep) {
+        return clusters;
+    }
+    dissolve(clusters, &keep)
+}
+
+/// Keeps the real clusters with `keep` set and turns the points of the others into noise, unless they also belong
+/// to a kept cluster. Kept clusters stay in their order and the noise group stays last.
+fn dissolve(mut clusters: Vec<Cluster>, keep: &[bool]) -> Vec<Cluster> {
+    let mut noise = match clusters.last() {
+        Some(cluster) if is_noise(cluster) => clusters.pop().expect("noise group exists"),
+        _ => Vec::new(),
+    };
+    let mut kept = Vec::with_capacity(clusters.len() + 1);
+    let mut dropped = Vec::new();
+    for (cluster, &keep) in clusters.into_iter().zip(keep) {
+        if keep {
+            kept.push(cluster);
+        } else {
+            dropped.extend(cluster.into_iter().map(|assignment| assignment.index));
+        }
+    }
+    let assigned: HashSet<usize> = kept.iter().flatten().map(|a| a.index).collect();
+    let noisy: HashSet<usize> = noise.iter().map(|a| a.index).collect();
+    dropped.sort_unstable();
+    dropped.dedup();
+    noise.extend(
+        dropped
+            .into_iter()
+            .filter(|index| !assigned.contains(index) && !noisy.contains(index))
+            .map(|index| Assignment {
+                index,
+                label: 1.0,
+                category: Category::Noise,
+                core_distance: None,
+                core_index: None,
+            }),
+    );
+    noise.sort_by_key(|assignment| assignment.index);
+    if !noise.is_empty() {
+        kept.push(noise);
+    }
+    kept
+}
+
+/// Moves every noise assignment into one trailing noise group, sorted by point index, and appends that group even
+/// if it is empty, so the last cluster is always the noise group. Clusters left without members are removed.
+pub fn compact_noise(clusters: &mut Vec<Cluster>) {
+    let mut noise = Vec::new();
+    for cluster in clusters.iter_mut() {
+        noise.extend(
+            cluster
+                .iter()
+                .filter(|assignment| assignment.category == Category::Noise)
+                .cloned(),
+        );
+        cluster.retain(|assignment| assignment.category != Category::Noise);
+    }
+    clusters.retain(|cluster| !cluster.is_empty());
+    noise.sort_by_key(|assignment| assignment.index);
+    noise.dedup_by_key(|assignment| assignment.index);
+    clusters.push(noise);
+}
+
+/// Combines several clusterings of the same `point_count` points into consensus clusters of the points that share
+/// a cluster in every clustering, and scores how much the clusterings agree on each point.
+///
+/// Each point counts as a member of the real cluster where it has the highest label (ties going to the earlier
+/// cluster). A point that is noise in any clustering is consensus noise, with the fraction of clusterings that
+/// agree it is noise as its score. Other points are members of the consensus cluster of all points with the same
+/// clusters, labeled with their lowest label and categorized as core points only if they are core points in every
+/// clustering. Their score is the mean size of the consensus cluster relative to their cluster in each clustering,
+/// so 1 means every clustering found exactly the consensus cluster.
+///
+/// Consensus clusters are ordered by their first point, followed by the noise group if there is any noise.
+pub fn consensus(runs: &[Vec<Cluster>], point_count: usize) -> (Vec<Cluster>, Vec<f64>) {
+    // the cluster, label and category each point has in each run, or None for noise
+    let mut hard = vec![vec![None; point_count]; runs.len()];
+    let mut sizes = Vec::with_capacity(runs.len());
+    for (run, clusters) in runs.iter().enumerate() {
+        for (cluster_index, cluster) in clusters.iter().enumerate() {
+            if is_noise(cluster) {
+                continue;
+            }
+            for assignment in cluster {
+                let best = &mut hard[run][assignment.index];
+                if best.is_none_or(|(_, label, _)| assignment.label > label) {
+                    *best = Some((cluster_index, assignment.label, assignment.category));
+                }
+            }
+        }
+        let mut run_sizes = vec![0usize; clusters.len()];
+        for &(cluster_index, _, _) in hard[run].iter().flatten() {
+            run_sizes[cluster_index] += 1;
+        }
+        sizes.push(run_sizes);
+    }
+
+    let mut groups: HashMap<Vec<usize>, usize> = HashMap::new();
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut noise = Vec::new();
+    let mut group_of = vec![None; point_count];
+    let mut agreement = vec![0.0; point_count];
+    for index in 0..point_count {
+        let assigned: Option<Vec<_>> = hard.iter().map(|run| run[index]).collect();
+        let Some(assigned) = assigned else {
+            let noisy = hard.iter().filter(|run| run[index].is_none()).count();
+            agreement[index] = noisy as f64 / runs.len() as f64;
+            noise.push(Assignment {
+                index,
+                label: 1.0,
+                category: Category::Noise,
+                core_distance: None,
+                core_index: None,
+            });
+            continue;
+        };
+        let key = assigned.iter().map(|&(cluster, _, _)| cluster).collect();
+        let group = *groups.entry(key).or_insert_with(|| {
+            clusters.push(Vec::new());
+            clusters.len() - 1
+        });
+        group_of[index] = Some(group);
+        clusters[group].push(Assignment {
+            index,
+            label: assigned
+                .iter()
+                .map(|&(_, label, _)| label)
+                .fold(1.0, f64::min),
+            category: if assigned
+                .iter()
+                .all(|&(_, _, category)| category == Category::Core)
+            {
+                Category::Core
+            } else {
+                Category::Border
+            },
+            core_distance: None,
+            core_index: None,
+        });
+    }
+    for (index, group) in group_of.into_iter().enumerate() {
+        let Some(group) = group else {
+            continue;
+        };
+        let size = clusters[group].len() as f64;
+        agreement[index] = hard
+            .iter()
+            .zip(&sizes)
+            .map(|(run, run_sizes)| {
+                let (cluster, _, _) = run[index].expect("consensus members are clustered");
+                size / run_sizes[cluster] as f64
+            })
+            .sum::<f64>()
+            / runs.len() as f64;
+    }
+    if !noise.is_empty() {
+        clusters.push(noise);
+    }
+    (clusters, agreement)
+}
+
+/// Relabels core points with a label below `threshold` as border points, keeping their labels and clusters.
+pub fn apply_core_threshold(clusters: &mut [Cluster], threshold: f64) {
+    for assignment in clusters.iter_mut().flatten() {
+        if assignment.category == Category::Core && assignment.label < threshold {
+            assignment.category = Category::Border;
+        }
+    }
+}
+
+/// Scales the label of every border point by its confidence `min(1, density / pts_max)`, where `densities` holds
+/// the fuzzy density of every border point, so border points in sparse regions get lower labels than those in
+/// dense regions at the same distance to a core point.
+pub fn weight_by_density(clusters: &mut [Cluster], densities: &HashMap<usize, f64>, pts_max: f64) {
+    for assignment in clusters.iter_mut().flatten() {
+        if assignment.category == Category::Border {
+            assignment.label *= (densities[&assignment.index] / pts_max).min(1.0);
+        }
+    }
+}
+
+/// Moves each point of the noise group whose nearest core point is within `reach` into that core point's cluster,
+/// as a [`Category::Reassigned`] point, and removes the noise group if that empties it.
+///
+/// Noise points are farther than `eps_max` from every core point, so `reach` only has an effect above `eps_max`.
+/// A reassigned point's label stretches the fuzzy distance ramp to `reach`, `(reach - d) / (reach - eps_min)` for
+/// a distance `d` to the core point, and is capped by the core point's label; it is small close to `reach` and
+/// has the distance and the core point as its core distance and core index.
+pub fn reassign_noise<P: MetricSpace>(
+    points: &[P],
+    clusters: &mut Vec<Cluster>,
+    eps_min: f64,
+    reach: f64,
+) {
+    let mut noise = match clusters.last() {
+        Some(cluster) if is_noise(cluster) => clusters.pop().expect("noise group exists"),
+        _ => return,
+    };
+    let mut reassigned = Vec::new();
+    noise.retain(|assignment| {
+        let query = &points[assignment.index];
+        let mut nearest = None;
+        let mut nearest_distance = reach;
+        for (cluster_index, cluster) in clusters.iter().enumerate() {
+            for core in cluster.iter().filter(|a| a.category == Category::Core) {
+                if let Some(distance) = query.distance_within(&points[core.index], nearest_distance)
+                {
+                    if nearest.is_none() || distance < nearest_distance {
+                        nearest = Some((cluster_index, core.index, core.label));
+                        nearest_distance = distance;
+                    }
+                }
+            }
+        }
+        let Some((cluster_index, core_index, core_label)) = nearest else {
+            return true;
+        };
+        let ramp = (reach - nearest_distance) / (reach - eps_min);
+        reassigned.push((
+            cluster_index,
+            Assignment {
+                index: assignment.index,
+                label: core_label.min(ramp),
+                category: Category::Reassigned,
+                core_distance: Some(nearest_distance),
+                core_index: Some(core_index),
+            },
+        ));
+        false
+    });
+    for (cluster_index, assignment) in reassigned {
+        clusters[cluster_index].push(assignment);
+    }
+    if !noise.is_empty() {
+        clusters.push(noise);
+    }
+}
+
+/// Returns the index of the member of `cluster` with the smallest total distance to all other members.
+///
+/// For clusters larger than [`MEDOID_SAMPLE_SIZE`], both the candidates and the members they are compared to are
+/// an evenly spaced sample of that size, so the result is an approximation. Ties go to the earlier member.
+///
+/// # Panics
+/// Panics if `cluster` is empty.
+pub fn medoid<P: MetricSpace>(points: &[P], cluster: &Cluster) -> usize {
+    let step = cluster.len().div_ceil(MEDOID_SAMPLE_SIZE);
+    let sample: Vec<usize> = cluster
+        .iter()
+        .step_by(step)
+        .map(|assignment| assignment.index)
+        .collect();
+
+    let mut best = (sample[0], f64::INFINITY);
+    for &candidate in &sample {
+        let total: f64 = sample
+            .iter()
+            .map(|&other| points[candidate].distance(&points[other]))
+            .sum();
+        if total < best.1 {
+            best = (candidate, total);
+        }
+    }
+    best.0
+}
+
+/// Returns the mean coordinates of the members of `cluster` in packed points with `dimensions` scalars each.
+pub fn centroid(data: &[PointScalar], dimensions: usize, cluster: &Cluster) -> Vec<PointScalar> {
+    let mut sum = vec![0f64; dimensions];
+    for assignment in cluster.iter() {
+        let point = &data[assignment.index * dimensions..(assignment.index + 1) * dimensions];
+        for (sum, &value) in sum.iter_mut().zip(point) {
+            *sum += value as f64;
+        }
+    }
+    sum.into_iter()
+        .map(|sum| (sum / cluster.len() as f64) as PointScalar)
+        .collect()
+}
+
+/// Returns for each dimension the share of the variance of cluster members that lies between `clusters` (their
+/// between-cluster sum of squares over the total sum of squares), from 0 if the dimension doesn't separate the
+/// clusters at all to 1 if their members only differ between clusters.
+///
+/// Points count once per cluster they belong to, the noise group is ignored, and dimensions without variance get 0.
+pub fn dimension_separation(
+    data: &[PointScalar],
+    dimensions: usize,
+    clusters: &[&Cluster],
+) -> Vec<f64> {
+    let member_count: usize = clusters.iter().map(|cluster| cluster.len()).sum();
+    let centroids: Vec<Vec<PointScalar>> = clusters
+        .iter()
+        .map(|cluster| centroid(data, dimensions, cluster))
+        .collect();
+    let mut mean = vec![0f64; dimensions];
+    for (cluster, centroid) in clusters.iter().zip(&centroids) {
+        for (mean, &value) in mean.iter_mut().zip(centroid) {
+            *mean += value as f64 * cluster.len() as f64 / member_count as f64;
+        }
+    }
+    let (mut between, mut total) = (vec![0f64; dimensions], vec![0f64; dimensions]);
+    for (cluster, centroid) in clusters.iter().zip(&centroids) {
+        for dim in 0..dimensions {
+            between[dim] += cluster.len() as f64 * (centroid[dim] as f64 - mean[dim]).powi(2);
+        }
+        for assignment in cluster.iter() {
+            let point = &data[assignment.index * dimensions..(assignment.index + 1) * dimensions];
+            for dim in 0..dimensions {
+                total[dim] += (point[dim] as f64 - mean[dim]).powi(2);
+            }
+        }
+    }
+    between
+        .into_iter()
+        .zip(total)
+        .map(|(between, total)| {
+            if total > 0. {
+                (between / total).min(1.)
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
+
+/// Returns the minimum and maximum coordinates of the members of `cluster` in packed points with `dimensions`
+/// scalars each.
+pub fn bounding_box(
+    data: &[PointScalar],
+    dimensions: usize,
+    cluster: &Cluster,
+) -> (Vec<PointScalar>, Vec<PointScalar>) {
+    let mut min = vec![PointScalar::INFINITY; dimensions];
+    let mut max = vec![PointScalar::NEG_INFINITY; dimensions];
+    for assignment in cluster.iter() {
+        let point = &data[assignment.index * dimensions..(assignment.index + 1) * dimensions];
+        for (dim, &value) in point.iter().enumerate() {
+            min[dim] = min[dim].min(value);
+            max[dim] = max[dim].max(value);
+        }
+    }
+    (min, max)
+}
+
+/// Returns the number of distinct members of `cluster` per unit of its [bounding box](bounding_box) volume, or
+/// infinity if the box has no volume, e.g. because all members have the same coordinate in some dimension.
+pub fn box_density(data: &[PointScalar], dimensions: usize, cluster: &Cluster) -> f64 {
+    let (min, max) = bounding_box(data, dimensions, cluster);
+    let volume: f64 = min
+        .iter()
+        .zip(&max)
+        .map(|(&min, &max)| (max - min) as f64)
+        .product();
+    let members: HashSet<usize> = cluster.iter().map(|assignment| assignment.index).collect();
+    if volume > 0. {
+        members.len() as f64 / volume
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Returns the most common of `values` (indexed by point) among the distinct members of `cluster` and the fraction
+/// of members that have it. Ties go to the smaller value.
+///
+/// # Panics
+/// Panics if `cluster` is empty or a member has no value.
+pub fn dominant_value(values: &[u32], cluster: &Cluster) -> (u32, f64) {
+    let members: HashSet<usize> = cluster.iter().map(|assignment| assignment.index).collect();
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for &member in &members {
+        *counts.entry(values[member]).or_default() += 1;
+    }
+    let (value, count) = counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .expect("cluster has members");
+    (value, count as f64 / members.len() as f64)
+}
+
+/// Clusters with more members than this get an approximate diameter from [`spread`].
+pub const EXACT_DIAMETER_LIMIT: usize = 1024;
+
+/// How far the members of a cluster spread out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    /// The largest distance between two members.
+    pub diameter: f64,
+    /// The mean distance of the members to the centroid.
+    pub mean_centroid_distance: f64,
+}
+
+/// Measures how far the members of `cluster` spread out around `centroid`.
+///
+/// For clusters larger than [`EXACT_DIAMETER_LIMIT`], the diameter is approximated as the distance from the member
+/// farthest from the centroid to the member farthest from that one. This never overestimates and, for a metric,
+/// is at least half of the true diameter.
+///
+/// # Panics
+/// Panics if `cluster` is empty.
+pub fn spread<P: MetricSpace>(points: &[P], centroid: &P, cluster: &Cluster) -> Spread {
+    let farthest_from = |from: &P| {
+        cluster
+            .iter()
+            .map(|assignment| (assignment.index, from.distance(&points[assignment.index])))
+            .fold((cluster[0].index, 0.), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    };
+    let mean_centroid_distance = cluster
+        .iter()
+        .map(|assignment| centroid.distance(&points[assignment.index]))
+        .sum::<f64>()
+        / cluster.len() as f64;
+    let diameter = if cluster.len() > EXACT_DIAMETER_LIMIT {
+        let (outermost, _) = farthest_from(centroid);
+        farthest_from(&points[outermost]).1
+    } else {
+        cluster
+            .iter()
+            .map(|assignment| farthest_from(&points[assignment.index]).1)
+            .fold(0., f64::max)
+    };
+    Spread {
+        diameter,
+        mean_centroid_distance,
+    }
+}
+
+/// Matches clusters to those of a previous run by greedily pairing the closest centroids first.
+///
+/// Returns, for each of `centroids`, the index of the matched entry of `previous`, or `None` if no unmatched
+/// previous centroid is within `max_distance`, e.g. for a cluster that newly appeared.
+pub fn match_clusters<P: MetricSpace>(
+    centroids: &[P],
+    previous: &[P],
+    max_distance: f64,
+) -> Vec<Option<usize>> {
+    let mut pairs = Vec::new();
+    for (i, centroid) in centroids.iter().enumerate() {
+        for (j, other) in previous.iter().enumerate() {
+            let distance = centroid.distance(other);
+            if distance <= max_distance {
+                pairs.push((distance, i, j));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut matches = vec![None; centroids.len()];
+    let mut taken = vec![false; previous.len()];
+    for (_, i, j) in pairs {
+        if matches[i].is_none() && !taken[j] {
+            matches[i] = Some(j);
+            taken[j] = true;
+        }
+    }
+    matches
+}
+
+/// Matches clusters to those of a previous run like [`match_clusters`], but optimally instead of greedily: among
+/// the matchings with the most pairs within `max_distance`, returns one with the smallest total distance between
+/// matched centroids (with the Hungarian algorithm, in cubic time).
+///
+/// Unlike the greedy matching, this doesn't let a close pair take a centroid that another cluster needs more, e.g.
+/// when clusters swap neighbors.
+pub fn match_clusters_optimal<P: MetricSpace>(
+    centroids: &[P],
+    previous: &[P],
+    max_distance: f64,
+) -> Vec<Option<usize>> {
+    let size = centroids.len().max(previous.len());
+    let mut distances = vec![None; size * size];
+    for (i, centroid) in centroids.iter().enumerate() {
+        for (j, other) in previous.iter().enumerate() {
+            distances[i * size + j] = centroid.distance_within(other, max_distance);
+        }
+    }
+    // a pair that can't be matched costs more than all possible pairs together, so every matching with fewer
+    // pairs costs more
+    let penalty = distances.iter().flatten().sum::<f64>() + 1.0;
+    let costs: Vec<f64> = distances
+        .iter()
+        .map(|distance| distance.unwrap_or(penalty))
+        .collect();
+    hungarian(&costs, size)
+        .into_iter()
+        .take(centroids.len())
+        .enumerate()
+        .map(|(i, j)| distances[i * size + j].map(|_| j))
+        .collect()
+}
+
+/// Returns, for each row of the row-major `size` × `size` matrix `costs`, the column assigned to it in an
+/// assignment with the smallest total cost.
+fn hungarian(costs: &[f64], size: usize) -> Vec<usize> {
+    // potentials and the row assigned to each column, 1-based with a virtual column 0
+    let mut row_potentials = vec![0.0; size + 1];
+    let mut column_potentials = vec![0.0; size + 1];
+    let mut assigned = vec![0usize; size + 1];
+    let mut way = vec![0usize; size + 1];
+    for row in 1..=size {
+        assigned[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; size + 1];
+        let mut used = vec![false; size + 1];
+        loop {
+            used[column] = true;
+            let current_row = assigned[column];
+            let mut delta = f64::INFINITY;
+            let mut next_column = 0;
+            for j in 1..=size {
+                if used[j] {
+                    continue;
+                }
+                let slack = costs[(current_row - 1) * size + j - 1]
+                    - row_potentials[current_row]
+                    - column_potentials[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next_column = j;
+                }
+            }
+            for j in 0..=size {
+                if used[j] {
+                    row_potentials[assigned[j]] += delta;
+                    column_potentials[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next_column;
+            if assigned[column] == 0 {
+                break;
+            }
+        }
+        // flip the augmenting path
+        while column != 0 {
+            let previous_column = way[column];
+            assigned[column] = assigned[previous_column];
+            column = previous_column;
+        }
+    }
+    let mut columns = vec![0; size];
+    for j in 1..=size {
+        columns[assigned[j] - 1] = j - 1;
+    }
+    columns
+}
+
+/// Returns the index (among `clusters`) of the cluster with the core point nearest to `query`, if that point is
+/// within `eps`.
+pub fn nearest_core_cluster<P: MetricSpace>(
+    points: &[P],
+    clusters: &[&Cluster],
+    query: &P,
+    eps: f64,
+) -> Option<usize> {
+    let mut nearest = None;
+    let mut nearest_distance = eps;
+    for (cluster_index, cluster) in clusters.iter().enumerate() {
+        for assignment in cluster.iter() {
+            if assignment.category != Category::Core {
+                continue;
+            }
+            if let Some(distance) =
+                query.distance_within(&points[assignment.index], nearest_distance)
+            {
+                if nearest.is_none() || distance < nearest_distance {
+                    nearest = Some(cluster_index);
+                    nearest_distance = distance;
+                }
+            }
+        }
+    }
+    nearest
+}
+
+/// Returns the points (sorted by index) whose clusters differ between two clusterings of the same `point_count`
+/// points, or that moved in or out of noise. A cluster of `clusters` counts as the cluster of `previous` that holds
+/// most of its members (see [`parent_clusters`]), so a cluster that grows or shrinks keeps its identity.
+pub fn changed_points(
+    previous: &[Cluster],
+    clusters: &[Cluster],
+    point_count: usize,
+) -> Vec<usize> {
+    let parents = parent_clusters(clusters, previous);
+    let memberships = |clusters: &[Cluster], id: &dyn Fn(usize) -> usize| {
+        let mut memberships = vec![Vec::new(); point_count];
+        for (cluster_index, cluster) in clusters.iter().enumerate() {
+            if is_noise(cluster) {
+                continue;
+            }
+            for assignment in cluster {
+                memberships[assignment.index].push(id(cluster_index));
+            }
+        }
+        for ids in &mut memberships {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        memberships
+    };
+    let before = memberships(previous, &|cluster_index| cluster_index);
+    let after = memberships(clusters, &|cluster_index| match parents[cluster_index] {
+        Some(parent) if !is_noise(&previous[parent]) => parent,
+        _ => previous.len() + cluster_index,
+    });
+    (0..point_count)
+        .filter(|&index| before[index] != after[index])
+        .collect()
+}
+
+/// Returns, for each of `children`, the index of the cluster in `parents` that holds most of its members, with
+/// ties going to the earlier parent, or `None` if no parent holds any of them.
+pub fn parent_clusters(children: &[Cluster], parents: &[Cluster]) -> Vec<Option<usize>> {
+    let mut containing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (parent_index, parent) in parents.iter().enumerate() {
+        for assignment in parent {
+            let entry = containing.entry(assignment.index).or_default();
+            if entry.last() != Some(&parent_index) {
+                entry.push(parent_index);
+            }
+        }
+    }
+    children
+        .iter()
+        .map(|child| {
+            let mut votes = vec![0usize; parents.len()];
+            for assignment in child {
+                for &parent in containing.get(&assignment.index).into_iter().flatten() {
+                    votes[parent] += 1;
+                }
+            }
+            let (best, &count) = votes
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|&(_, count)| count)?;
+            (count > 0).then_some(best)
+        })
+        .collect()
+}
+
+/// Saturation of the colors suggested by [`spread_colors`].
+pub const COLOR_SATURATION: f64 = 0.65;
+/// Lightness of the colors suggested by [`spread_colors`].
+pub const COLOR_LIGHTNESS: f64 = 0.5;
+
+/// Suggests one distinct RGB color per cluster by spreading hues evenly around the HSL wheel.
+///
+/// The largest cluster gets hue 0° (red) and smaller clusters follow in order of decreasing size; clusters of
+/// equal size keep the order of `sizes`. Colors are returned in the order of `sizes`.
+pub fn spread_colors(sizes: &[usize]) -> Vec<[u8; 3]> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]));
+    let mut colors = vec![[0; 3]; sizes.len()];
+    for (rank, &cluster) in order.iter().enumerate() {
+        let hue = rank as f64 / sizes.len() as f64 * 360.;
+        colors[cluster] = hsl_to_rgb(hue, COLOR_SATURATION, COLOR_LIGHTNESS);
+    }
+    colors
+}
+
+/// Converts a color from HSL (hue in degrees, saturation and lightness in `0..=1`) to RGB.
+pub fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [u8; 3] {
+    let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+    let sector = hue.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (sector % 2. - 1.).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+    let m = lightness - chroma / 2.;
+    [r, g, b].map(|c| ((c + m) * 255.).round() as u8)
+}
+
+/// Returns the membership of every point in every real cluster (excluding the noise group) as a row-major
+/// `point_count × cluster_count` matrix, together with the cluster count.
+///
+/// A point's membership in a cluster is its soft label there; points that are only noise have no membership.
+/// With `normalize`, each point's row is scaled to sum to 1 unless it is all zero.
+pub fn membership_matrix(
+    clusters: &[Cluster],
+    point_count: usize,
+    normalize: bool,
+) -> (Vec<f32>, usize) {
+    let clusters: Vec<&Cluster> = clusters.iter().filter(|c| !is_noise(c)).collect();
+    let cluster_count = clusters.len();
+    let mut matrix = vec![0f32; point_count * cluster_count];
+    for (cluster_index, cluster) in clusters.iter().enumerate() {
+        for assignment in cluster.iter() {
+            let cell = &mut matrix[assignment.index * cluster_count + cluster_index];
+            *cell = cell.max(assignment.label as f32);
+        }
+    }
+    if normalize && cluster_count > 0 {
+        for row in matrix.chunks_exact_mut(cluster_count) {
+            let sum: f32 = row.iter().sum();
+            if sum > 0. {
+                row.iter_mut().for_each(|membership| *membership /= sum);
+            }
+        }
+    }
+    (matrix, cluster_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position(f64);
+
+    impl MetricSpace for Position {
+        fn distance(&self, other: &Self) -> f64 {
+            (other.0 - self.0).abs()
+        }
+    }
+
+    fn assignment(index: usize, label: f64, category: Category) -> Assignment {
+        Assignment {
+            index,
+            label,
+            category,
+            core_distance: None,
+            core_index: None,
+        }
+    }
+
+    #[test]
+    fn low_confidence_clusters_are_dissolved() {
+        let confident = vec![
+            assignment(0, 1.0, Category::Core),
+            assignment(1, 0.9, Category::Core),
+            assignment(2, 0.4, Category::Border),
+        ];
+        // a single weak core point with faint borders, one of which is shared with the confident cluster
+        let mushy = vec![
+            assignment(3, 0.3, Category::Core),
+            assignment(4, 0.1, Category::Border),
+            assignment(2, 0.1, Category::Border),
+        ];
+        let noise = vec![assignment(5, 1.0, Category::Noise)];
+        let clusters = vec![confident.clone(), mushy.clone(), noise.clone()];
+
+        assert_eq!(keep_confident(clusters.clone(), 0.1), clusters);
+        let kept = keep_confident(clusters, 0.5);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0], confident);
+        let noise: Vec<usize> = kept[1].iter().map(|a| a.index).collect();
+        assert_eq!(noise, [3, 4, 5]);
+        assert!(is_noise(&kept[1]));
+    }
+
+    #[test]
+    fn optimal_matching_beats_greedy() {
+        let previous = [Position(0.), Position(3.)];
+        // greedily, the first cluster takes the closest previous one and leaves the second one far away
+        let centroids = [Position(1.), Position(-1.5), Position(20.)];
+        assert_eq!(
+            match_clusters(&centroids, &previous, 5.),
+            [Some(0), Some(1), None]
+        );
+        assert_eq!(
+            match_clusters_optimal(&centroids, &previous, 5.),
+            [Some(1), Some(0), None]
+        );
+        // more pairs come first, even if they are farther apart in total
+        assert_eq!(
+            match_clusters_optimal(&centroids, &previous, 2.),
+            [Some(1), Some(0), None]
+        );
+        assert_eq!(
+            match_clusters_optimal(&centroids[..1], &previous, 5.),
+            [Some(0)]
+        );
+        assert!(match_clusters_optimal::<Position>(&[], &previous, 5.).is_empty());
+    }
+}
@@ -0,0 +1,34 @@
+//! Synthetic data for benchmarks, available with the `bench` feature.
+
+use crate::packing::PointScalar;
+use crate::rng::Rng;
+
+/// Generates `point_count` packed points with `dimensions` scalars each, forming `cluster_count` Gaussian blobs.
+///
+/// Blob centers are uniformly distributed in the unit cube and every coordinate deviates from its center with
+/// standard deviation `spread`. Point `i` belongs to blob `i % cluster_count`, so blobs are interleaved and of
+/// equal size. The same arguments always generate the same points.
+pub fn generate_clusters(
+    point_count: usize,
+    dimensions: usize,
+    cluster_count: usize,
+    spread: f64,
+    seed: u64,
+) -> Vec<PointScalar> {
+    if dimensions == 0 {
+        return Vec::new();
+    }
+    let mut rng = Rng::new(seed);
+    let centers: Vec<f64> = (0..cluster_count.max(1) * dimensions)
+        .map(|_| rng.uniform())
+        .collect();
+    let mut data = Vec::with_capacity(point_count * dimensions);
+    for (_, center) in (0..point_count).zip(centers.chunks_exact(dimensions).cycle()) {
+        data.extend(
+            center
+                .iter()
+                .map(|c| (c + rng.gaussian() * spread) as PointScalar),
+        );
+    }
+    data
+}
@@ -0,0 +1,205 @@
+//! Non-fatal data quality checks.
+//!
+//! These don't stop a clustering from running, but usually point at a problem with the input.
+
+use std::collections::HashSet;
+
+use crate::analysis;
+use crate::fuzzy_dbscan::Cluster;
+use crate::packing::PointScalar;
+
+/// Share of noise points above which [`noise_warning`] flags a clustering.
+pub const NOISE_WARNING_SHARE: f64 = 0.8;
+
+/// Checks packed point data for non-finite values, duplicate points and constant dimensions.
+///
+/// Returns human-readable warnings; an empty list means nothing was found.
+pub fn data_warnings(data: &[PointScalar], dimensions: usize) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if dimensions == 0 {
+        return warnings;
+    }
+    let points = data.chunks_exact(dimensions);
+    let point_count = points.len();
+
+    let non_finite = points
+        .clone()
+        .filter(|point| point.iter().any(|value| !value.is_finite()))
+        .count();
+    if non_finite > 0 {
+        warnings.push(format!(
+            "{} of {} points have non-finite coordinates",
+            non_finite, point_count
+        ));
+    }
+
+    let mut seen = HashSet::with_capacity(point_count);
+    let duplicates = points
+        .clone()
+        .filter(|&point| !seen.insert(Coordinates(point)))
+        .count();
+    if duplicates > 0 {
+        warnings.push(format!(
+            "{} points have the same coordinates as an earlier point",
+            duplicates
+        ));
+    }
+
+    if point_count > 1 {
+        for dim in 0..dimensions {
+            let first = data[dim];
+            if points.clone().all(|point| point[dim] == first) {
+                warnings.push(format!(
+                    "dimension {} has the same value for every point",
+                    dim
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Estimates the peak number of bytes [`data_warnings`] uses for `point_count` points besides the points
+/// themselves, i.e. the hash set that finds duplicates.
+pub fn estimated_memory(point_count: usize) -> usize {
+    // hash tables keep at least an eighth of their buckets free and round their number up to a power of two, and
+    // each bucket has a control byte
+    let buckets = (point_count * 8 / 7).next_power_of_two();
+    buckets * (std::mem::size_of::<Coordinates>() + 1)
+}
+
+/// The coordinates of a point, compared bit by bit so they can be hashed.
+struct Coordinates<'a>(&'a [PointScalar]);
+
+impl PartialEq for Coordinates<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0)
+                .all(|(a, b)| a.to_bits() == b.to_bits())
+    }
+}
+
+impl Eq for Coordinates<'_> {}
+
+impl std::hash::Hash for Coordinates<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for value in self.0 {
+            value.to_bits().hash(state);
+        }
+    }
+}
+
+/// Dimension counts tried by [`dimension_warning`] when the data seems to hold a single point or one-dimensional
+/// points, from most to least common.
+const LIKELY_DIMENSIONS: [usize; 3] = [2, 3, 4];
+
+/// Checks whether `dimensions` is plausible for `scalar_count` scalars, since a wrong value only fails if it
+/// doesn't divide the length. Fewer points than dimensions usually means that the point count was passed as
+/// `dimensions` (which makes the point count the actual dimensions) or that `dimensions` is the total length.
+/// One dimension reads the largest possible number of points, and usually means that the parameter was left at 1
+/// for 2D or 3D data, so it is flagged if the length is divisible by 2, 3 or 4; that also
+/// flags genuinely one-dimensional data of such a length. Only looks at the length, so it can't catch every
+/// mistake, e.g. 3 for 2D data with a length divisible by 6.
+pub fn dimension_warning(scalar_count: usize, dimensions: usize) -> Option<String> {
+    if dimensions == 0 || !scalar_count.is_multiple_of(dimensions) {
+        return None;
+    }
+    let point_count = scalar_count / dimensions;
+    if dimensions == 1 && point_count > 1 {
+        let likely = LIKELY_DIMENSIONS
+            .into_iter()
+            .find(|&candidate| scalar_count.is_multiple_of(candidate))?;
+        return Some(format!(
+            "{} values read as {} one-dimensional points; check the dimensions parameter (maybe {} dimensions \
+             were intended?)",
+            scalar_count, point_count, likely
+        ));
+    }
+    if point_count == 0 || point_count >= dimensions {
+        return None;
+    }
+    let likely = if point_count > 1 {
+        Some(point_count)
+    } else {
+        LIKELY_DIMENSIONS
+            .into_iter()
+            .find(|&candidate| candidate < dimensions && scalar_count.is_multiple_of(candidate))
+    };
+    let mut warning = format!(
+        "{} values read as {} points with {} dimensions each; check the dimensions parameter",
+        scalar_count, point_count, dimensions
+    );
+    if let Some(likely) = likely {
+        warning += &format!(" (maybe {} dimensions were intended?)", likely);
+    }
+    Some(warning)
+}
+
+/// Checks whether more than [`NOISE_WARNING_SHARE`] of the clustered points are noise, which usually means that
+/// eps is too small or the density thresholds are too high. Points left out of the clustering (e.g. by sampling)
+/// don't count.
+pub fn noise_warning(clusters: &[Cluster]) -> Option<String> {
+    let noise = clusters
+        .last()
+        .filter(|cluster| analysis::is_noise(cluster))
+        .map_or(0, |cluster| cluster.len());
+    let point_count = clusters
+        .iter()
+        .flatten()
+        .map(|assignment| assignment.index)
+        .collect::<HashSet<_>>()
+        .len();
+    if point_count == 0 || noise as f64 <= NOISE_WARNING_SHARE * point_count as f64 {
+        return None;
+    }
+    Some(format!(
+        "{}% of points classified as noise; consider increasing eps or decreasing pts_min",
+        (100 * noise) / point_count
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimension_warning_suggests_the_likely_dimensions() {
+        assert_eq!(dimension_warning(200, 2), None);
+        // 100 2D points read with the point count as dimensions
+        let swapped = dimension_warning(200, 100).unwrap();
+        assert!(swapped.contains("maybe 2 dimensions"), "{}", swapped);
+        // the whole buffer as one point
+        let single = dimension_warning(300, 300).unwrap();
+        assert!(single.contains("maybe 2 dimensions"), "{}", single);
+        assert!(!dimension_warning(7, 7).unwrap().contains("maybe"));
+    }
+
+    #[test]
+    fn dimension_warning_flags_one_dimension() {
+        // 100 2D points read as 200 1D points
+        let flat = dimension_warning(200, 1).unwrap();
+        assert!(flat.contains("maybe 2 dimensions"), "{}", flat);
+        let flat = dimension_warning(9, 1).unwrap();
+        assert!(flat.contains("maybe 3 dimensions"), "{}", flat);
+        // a length no other dimension count divides
+        assert_eq!(dimension_warning(7, 1), None);
+        assert_eq!(dimension_warning(1, 1), None);
+    }
+
+    #[test]
+    fn data_warnings_count_duplicates_bit_by_bit() {
+        let data = [0.0, 1.0, 0.0, 1.0, -0.0, 1.0, 0.0, 1.0, 2.0, 3.0];
+        let warnings = data_warnings(&data, 2);
+        assert!(
+            warnings
+                .contains(&"2 points have the same coordinates as an earlier point".to_string()),
+            "{:?}",
+            warnings
+        );
+        assert!(data_warnings(&data[..2], 1).is_empty());
+    }
+}
@@ -11,10 +11,13 @@
 //!
 //! An implementation of the FuzzyDBSCAN algorithm.
 //!
+//! This module is the stable Rust API of the crate and doesn't depend on the wasm bindings:
+//! implement [`MetricSpace`] for your own point type and pass a slice of points to [`FuzzyDBSCAN::cluster`].
+//!
 //! # Example
 //!
 //! ```rust
-//! extern crate fuzzy_dbscan;
+//! use d3st_wasm::fuzzy_dbscan;
 //!
 //! #[derive(Debug)]
 //! struct Point {
@@ -47,39 +50,78 @@
 //! }
 //! ```
 
-use std::collections::HashSet;
-use std::hash::Hash;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::f64;
 
-fn take_arbitrary<T: Hash + Eq + Copy>(set: &mut HashSet<T>) -> Option<T> {
-    let key_copy = if let Some(key_ref) = set.iter().next() {
-        Some(*key_ref)
-    } else {
-        None
-    };
-    if let Some(key) = key_copy {
-        set.take(&key)
-    } else {
-        None
-    }
-}
+use crate::ClusterError;
 
 /// A trait to compute distances between points.
 pub trait MetricSpace: Sized {
     /// Returns the distance between `self` and `other`.
     fn distance(&self, other: &Self) -> f64;
+
+    /// Returns the distance between `self` and `other` if it is at most `threshold`.
+    ///
+    /// Neighbor queries use this instead of [`distance`](MetricSpace::distance), so implementations can stop
+    /// early once the distance is known to exceed `threshold`.
+    fn distance_within(&self, other: &Self, threshold: f64) -> Option<f64> {
+        let distance = self.distance(other);
+        (distance <= threshold).then_some(distance)
+    }
+
+    /// Returns the position of the point on a line if points of this type are one-dimensional, i.e. the
+    /// distance between two points only grows as their positions move apart.
+    ///
+    /// When every point has a position, [`cluster`](FuzzyDBSCAN::cluster) sorts the points once and finds
+    /// neighbors by walking outwards from each point instead of comparing it with all others. The clusters are
+    /// the same either way.
+    fn line_position(&self) -> Option<f64> {
+        None
+    }
+
+    /// Returns the coordinates of the point if the distance between two points of this type is at least the
+    /// difference of their coordinates along each dimension, as for the Euclidean distance.
+    ///
+    /// When every point has coordinates (and no [line position](MetricSpace::line_position)),
+    /// [`cluster`](FuzzyDBSCAN::cluster) builds a k-d tree of the points and finds neighbors by descending into the
+    /// parts of space within eps of each point instead of comparing it with all others. The clusters are the same
+    /// either way.
+    fn coordinates(&self) -> Option<Vec<f64>> {
+        None
+    }
+
+    /// Returns how much the point counts towards fuzzy densities (see [`FuzzyDBSCAN`]), e.g. the number of
+    /// observations an aggregated point stands for. Defaults to 1, so every point counts once.
+    fn weight(&self) -> f64 {
+        1.0
+    }
 }
 
 /// A high-level classification, as defined by the FuzzyDBSCAN algorithm.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Category {
     Core,
     Border,
     Noise,
+    /// A noise point that was attached to a nearby cluster after clustering, see
+    /// [`reassign_noise`](crate::analysis::reassign_noise).
+    Reassigned,
+}
+
+impl Category {
+    /// Returns the category as a lowercase word, e.g. `"core"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Core => "core",
+            Category::Border => "border",
+            Category::Noise => "noise",
+            Category::Reassigned => "reassigned",
+        }
+    }
 }
 
 /// An element of a [cluster](Cluster).
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Assignment {
     /// The point index.
     pub index: usize,
@@ -87,20 +129,43 @@ pub struct Assignment {
     pub label: f64,
     /// A high-level category.
     pub category: Category,
+    /// For border points, the distance to the core point that determined `label`.
+    pub core_distance: Option<f64>,
+    /// For border points, the index of the core point that determined `label`.
+    pub core_index: Option<usize>,
 }
 
 /// A group of [assigned](Assignment) points.
+///
+/// Noise points are collected in a trailing cluster whose assignments all have [`Category::Noise`].
 pub type Cluster = Vec<Assignment>;
 
 /// An instance of the FuzzyDBSCAN algorithm.
 ///
 /// Note that when setting `eps_min = eps_max` and `pts_min = pts_max` the algorithm will reduce to classic DBSCAN.
+///
+/// # Fuzzy density
+/// The density of a point is 1 (the point itself) plus the fuzzy count of its neighbors: a neighbor within
+/// `eps_min` counts 1, one beyond `eps_max` counts 0, and one in between counts linearly less with increasing
+/// distance. Densities are therefore real numbers, and so are `pts_min` and `pts_max`: a point with a density
+/// below `pts_min` is not a core point, one with at least `pts_max` is a core point with label 1, and in between
+/// the label rises linearly. Fractional values are meaningful and shift where that ramp starts and ends.
+///
+/// Points with a [`weight`](MetricSpace::weight) other than 1 count that many times, both for their own density
+/// and for their neighbors'.
+///
+/// The parameters are plain data and [`cluster`](FuzzyDBSCAN::cluster) only borrows `self`, so one instance can be
+/// shared between threads (e.g. behind an `Arc`) and used for concurrent clusterings.
+#[derive(Debug, Clone)]
 pub struct FuzzyDBSCAN {
     /// The minimum fuzzy local neighborhood radius.
     pub eps_min: f64,
     /// The maximum fuzzy local neighborhood radius.
     pub eps_max: f64,
     /// The minimum fuzzy neighborhood density (number of points).
+    ///
+    /// The density of a point includes the point itself with its [weight](MetricSpace::weight), so values below
+    /// the smallest weight (1 for unweighted points) make every point a core point.
     pub pts_min: f64,
     /// The maximum fuzzy neighborhood density (number of points).
     pub pts_max: f64,
@@ -108,119 +173,647 @@ pub struct FuzzyDBSCAN {
 
 impl FuzzyDBSCAN {
     /// Clusters a list of `points`.
+    ///
+    /// Assignment indices refer to positions in `points`.
     pub fn cluster<P: MetricSpace>(&self, points: &[P]) -> Vec<Cluster> {
-        self.fuzzy_dbscan(points)
+        self.cluster_seeded(points, &[])
+    }
+
+    /// Clusters a list of `points` like [`cluster`](FuzzyDBSCAN::cluster), but first grows one cluster from each
+    /// group of `seeds` that share a hint, see [`ClusterState::with_seeds`].
+    pub fn cluster_seeded<P: MetricSpace>(
+        &self,
+        points: &[P],
+        seeds: &[(usize, u32)],
+    ) -> Vec<Cluster> {
+        let neighborhoods = Neighborhoods::new(points);
+        let mut state = ClusterState::with_seeds(points.len(), seeds);
+        self.step_with(points, &mut state, usize::MAX, &neighborhoods);
+        state.into_clusters()
+    }
+
+    /// Clusters `points` once with each of `levels`, each time continuing a copy of `initial` (e.g. a state with
+    /// seeds and excluded core points) until it is done. Neighborhoods are searched only once, with the largest
+    /// `eps_max`, and narrowed down for the other levels.
+    ///
+    /// The neighborhoods of all points are kept in memory meanwhile. This relies on points within some radius of
+    /// each other also being within every larger radius.
+    pub fn cluster_levels<P: MetricSpace>(
+        levels: &[FuzzyDBSCAN],
+        points: &[P],
+        initial: &ClusterState,
+    ) -> Vec<Vec<Cluster>> {
+        Self::cluster_levels_indexed(levels, points, initial, &SpatialIndex::new(points))
+    }
+
+    /// Clusters like [`cluster_levels`](FuzzyDBSCAN::cluster_levels), but searches neighborhoods with an `index`
+    /// of `points` that was built before, e.g. to cluster the same points with different parameters.
+    pub fn cluster_levels_indexed<P: MetricSpace>(
+        levels: &[FuzzyDBSCAN],
+        points: &[P],
+        initial: &ClusterState,
+        index: &SpatialIndex,
+    ) -> Vec<Vec<Cluster>> {
+        let search = &index.0;
+        let widest = match levels {
+            [] => return Vec::new(),
+            [level] => {
+                let mut state = initial.clone();
+                level.step_with(points, &mut state, usize::MAX, search);
+                return vec![state.into_clusters()];
+            }
+            _ => levels
+                .iter()
+                .max_by(|a, b| a.eps_max.total_cmp(&b.eps_max))
+                .unwrap(),
+        };
+        let lists = (0..points.len())
+            .map(|point_index| widest.region_query(points, point_index, search))
+            .collect();
+        let neighborhoods = Neighborhoods::Lists(lists);
+        levels
+            .iter()
+            .map(|level| {
+                let mut state = initial.clone();
+                level.step_with(points, &mut state, usize::MAX, &neighborhoods);
+                state.into_clusters()
+            })
+            .collect()
+    }
+
+    /// Returns the number of clusters and noise points of a clustering of `points` with each of `levels`, like
+    /// [`cluster`](FuzzyDBSCAN::cluster) would find them, but much faster for many levels, and the level at which
+    /// each point first joins a cluster.
+    ///
+    /// The distances within the largest `eps_max` are computed once. Going through the levels by increasing
+    /// `eps_max`, pairs of points are added in order of their distance and core points are merged into clusters
+    /// with a union-find. This relies on densities only growing with eps.
+    ///
+    /// # Panics
+    /// Panics if the levels have different `pts_min` or `pts_max`, or if a level with a larger `eps_max` has a
+    /// smaller `eps_min` (e.g. they don't keep the ratio of `eps_min` to `eps_max`).
+    pub fn sweep_eps<P: MetricSpace>(levels: &[FuzzyDBSCAN], points: &[P]) -> Sweep {
+        Self::sweep_eps_indexed(levels, points, &SpatialIndex::new(points))
+    }
+
+    /// Sweeps like [`sweep_eps`](FuzzyDBSCAN::sweep_eps), but searches neighborhoods with an `index` of `points`
+    /// that was built before.
+    pub fn sweep_eps_indexed<P: MetricSpace>(
+        levels: &[FuzzyDBSCAN],
+        points: &[P],
+        index: &SpatialIndex,
+    ) -> Sweep {
+        let mut order: Vec<usize> = (0..levels.len()).collect();
+        order.sort_by(|&a, &b| levels[a].eps_max.total_cmp(&levels[b].eps_max));
+        for pair in order.windows(2) {
+            let (a, b) = (&levels[pair[0]], &levels[pair[1]]);
+            assert!(
+                a.eps_min <= b.eps_min && a.pts_min == b.pts_min && a.pts_max == b.pts_max,
+                "sweep levels must only differ in growing eps"
+            );
+        }
+        let Some(&widest) = order.last() else {
+            return Sweep {
+                levels: Vec::new(),
+                join_levels: vec![None; points.len()],
+            };
+        };
+        let point_count = points.len();
+        let search = &index.0;
+        // (distance, index) of the neighbors of each point within the largest eps_max, ordered by index like the
+        // neighborhoods of a clustering, so densities are summed in the same order
+        let neighbors: Vec<Vec<(f64, usize)>> = (0..point_count)
+            .map(|point_index| {
+                levels[widest]
+                    .region_query(points, point_index, search)
+                    .into_iter()
+                    .map(|neighbor_index| {
+                        let distance = points[point_index].distance(&points[neighbor_index]);
+                        (distance, neighbor_index)
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut pairs: Vec<(f64, usize, usize)> = neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(a, list)| {
+                list.iter()
+                    .filter(move |&&(_, b)| a < b)
+                    .map(move |&(distance, b)| (distance, a, b))
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut parents: Vec<usize> = (0..point_count).collect();
+        let mut core = vec![false; point_count];
+        let mut covered = vec![false; point_count];
+        let (mut cluster_count, mut next_pair) = (0, 0);
+        let mut results = vec![
+            SweepLevel {
+                cluster_count: 0,
+                noise_count: 0,
+            };
+            levels.len()
+        ];
+        let mut join_levels = vec![None; point_count];
+        for &level_index in &order {
+            let level = &levels[level_index];
+            let eps = level.eps_max;
+            while let Some(&(distance, a, b)) = pairs.get(next_pair) {
+                if distance > eps {
+                    break;
+                }
+                next_pair += 1;
+                if core[a] && core[b] && union(&mut parents, a, b) {
+                    cluster_count -= 1;
+                }
+                covered[b] |= core[a];
+                covered[a] |= core[b];
+            }
+            let new_cores: Vec<usize> = (0..point_count)
+                .filter(|&point_index| {
+                    let density = points[point_index].weight()
+                        + neighbors[point_index]
+                            .iter()
+                            .filter(|&&(distance, _)| distance <= eps)
+                            .fold(0.0, |sum, &(distance, neighbor_index)| {
+                                sum + points[neighbor_index].weight() * level.mu(distance)
+                            });
+                    !core[point_index] && level.mu_min_p(density) > 0.0
+                })
+                .collect();
+            for &point_index in &new_cores {
+                core[point_index] = true;
+                cluster_count += 1;
+            }
+            for &point_index in &new_cores {
+                covered[point_index] = true;
+                for &(distance, neighbor_index) in &neighbors[point_index] {
+                    if distance > eps {
+                        continue;
+                    }
+                    covered[neighbor_index] = true;
+                    if core[neighbor_index] && union(&mut parents, point_index, neighbor_index) {
+                        cluster_count -= 1;
+                    }
+                }
+            }
+            results[level_index] = SweepLevel {
+                cluster_count,
+                noise_count: covered.iter().filter(|&&covered| !covered).count(),
+            };
+            // points stay covered as eps grows, so the first level that covers a point is the one it joins at
+            for (join_level, &covered) in join_levels.iter_mut().zip(&covered) {
+                if covered && join_level.is_none() {
+                    *join_level = Some(level_index);
+                }
+            }
+        }
+        Sweep {
+            levels: results,
+            join_levels,
+        }
+    }
+
+    /// Returns for each of `points` whether it is a core point, i.e. whether its fuzzy density reaches `pts_min`.
+    ///
+    /// This matches the core points of [`cluster`](FuzzyDBSCAN::cluster) without expanding clusters.
+    pub fn core_mask<P: MetricSpace>(&self, points: &[P]) -> Vec<bool> {
+        self.core_labels(points)
+            .into_iter()
+            .map(|label| label > 0.0)
+            .collect()
+    }
+
+    /// Returns for each of `points` the label it has as a core point: 0 below `pts_min`, rising linearly to 1 at
+    /// `pts_max`.
+    pub fn core_labels<P: MetricSpace>(&self, points: &[P]) -> Vec<f64> {
+        let all: Vec<usize> = (0..points.len()).collect();
+        self.densities(points, &all)
+            .into_iter()
+            .map(|density| self.mu_min_p(density))
+            .collect()
+    }
+
+    /// Returns the label a point with the fuzzy `density` (see [`FuzzyDBSCAN`]) has as a core point: 0 below
+    /// `pts_min`, rising linearly to 1 at `pts_max`.
+    pub fn density_label(&self, density: f64) -> f64 {
+        self.mu_min_p(density)
+    }
+
+    /// Returns the fuzzy density (see [`FuzzyDBSCAN`]) of the points at `indices` among `points`.
+    pub fn densities<P: MetricSpace>(&self, points: &[P], indices: &[usize]) -> Vec<f64> {
+        let neighborhoods = Neighborhoods::new(points);
+        indices
+            .iter()
+            .map(|&point_index| {
+                let neighbor_indices = self.region_query(points, point_index, &neighborhoods);
+                self.density(point_index, &neighbor_indices, points)
+            })
+            .collect()
+    }
+
+    /// Updates `clusters`, a clustering of the same points with `self` by [`cluster`](FuzzyDBSCAN::cluster), after
+    /// the points at `changed` moved from `previous` (one point per changed index) to their positions in `points`.
+    /// Returns the same clusters as clustering `points` again.
+    ///
+    /// Only points within `eps_max` of a changed point before or after the move can change their density. Clusters
+    /// without such a point keep their core points, borders and labels, as a new link to them would have to pass
+    /// through one. The other clusters are grown again from their core points and the new ones, which queries the
+    /// neighborhoods of their members only. Clusters are ordered by their first core point, like a full clustering
+    /// finds them, and points in no cluster are noise.
+    ///
+    /// # Panics
+    /// Panics if `changed` and `previous` have different lengths.
+    pub fn update<P: MetricSpace>(
+        &self,
+        points: &[P],
+        changed: &[usize],
+        previous: &[P],
+        clusters: &[Cluster],
+    ) -> Vec<Cluster> {
+        self.update_indexed(
+            points,
+            changed,
+            previous,
+            clusters,
+            &SpatialIndex::new(points),
+        )
+    }
+
+    /// Updates clusters like [`update`](FuzzyDBSCAN::update), but searches neighborhoods, also those of the
+    /// previous positions, with an `index` of `points` after the move, e.g. the index of the points before it
+    /// after [`move_points`](SpatialIndex::move_points).
+    pub fn update_indexed<P: MetricSpace>(
+        &self,
+        points: &[P],
+        changed: &[usize],
+        previous: &[P],
+        clusters: &[Cluster],
+        index: &SpatialIndex,
+    ) -> Vec<Cluster> {
+        assert_eq!(
+            changed.len(),
+            previous.len(),
+            "update needs one previous point per changed index"
+        );
+        let neighborhoods = &index.0;
+        let mut affected = vec![false; points.len()];
+        for (&point_index, previous_point) in changed.iter().zip(previous) {
+            affected[point_index] = true;
+            for neighbor_index in self.region_query(points, point_index, neighborhoods) {
+                affected[neighbor_index] = true;
+            }
+            for neighbor_index in neighborhoods.search(points, previous_point, self.eps_max, None) {
+                affected[neighbor_index] = true;
+            }
+        }
+
+        let mut kept = Vec::new();
+        let mut candidates: Vec<usize> = (0..points.len()).filter(|&i| affected[i]).collect();
+        for cluster in clusters {
+            if cluster
+                .iter()
+                .all(|assignment| assignment.category == Category::Noise)
+            {
+                continue;
+            }
+            if cluster.iter().all(|assignment| !affected[assignment.index]) {
+                kept.push(cluster.clone());
+            } else {
+                candidates.extend(cluster.iter().map(|assignment| assignment.index));
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let non_core = vec![false; points.len()];
+        let constraints = Constraints::default();
+        let mut grown = vec![false; points.len()];
+        for point_index in candidates {
+            if grown[point_index] {
+                continue;
+            }
+            let neighbor_indices = self.region_query(points, point_index, neighborhoods);
+            let point_label = self.core_label(points, point_index, &neighbor_indices, &non_core);
+            if point_label == 0.0 {
+                continue;
+            }
+            let mut expansion = Expansion::new(
+                point_index,
+                point_label,
+                neighbor_indices,
+                points.len(),
+                &constraints,
+            );
+            while let Some(neighbor_index) = expansion.queue.pop_front() {
+                self.expand_cluster_fuzzy(
+                    points,
+                    &mut expansion,
+                    neighbor_index,
+                    neighborhoods,
+                    &non_core,
+                    &constraints,
+                );
+            }
+            let cluster = self.finish_cluster(points, expansion);
+            for assignment in &cluster {
+                if assignment.category == Category::Core {
+                    grown[assignment.index] = true;
+                }
+            }
+            kept.push(cluster);
+        }
+        // a full clustering starts each cluster at its first core point, which is listed first
+        kept.sort_by_key(|cluster| cluster[0].index);
+
+        let mut clustered = vec![false; points.len()];
+        for assignment in kept.iter().flatten() {
+            clustered[assignment.index] = true;
+        }
+        let noise: Cluster = (0..points.len())
+            .filter(|&point_index| !clustered[point_index])
+            .map(|index| Assignment {
+                index,
+                category: Category::Noise,
+                label: 1.0,
+                core_distance: None,
+                core_index: None,
+            })
+            .collect();
+        if !noise.is_empty() {
+            kept.push(noise);
+        }
+        kept
+    }
+
+    /// Advances a clustering of `points` by at most `max_points` neighborhood queries.
+    ///
+    /// `state` must have been created for `points` and only be stepped with the same parameters. Returns whether
+    /// the clustering is done; then [`ClusterState::into_clusters`] returns the same result as
+    /// [`cluster`](FuzzyDBSCAN::cluster).
+    pub fn step<P: MetricSpace>(
+        &self,
+        points: &[P],
+        state: &mut ClusterState,
+        max_points: usize,
+    ) -> bool {
+        self.step_with(points, state, max_points, &Neighborhoods::Scan)
     }
 }
 
 impl FuzzyDBSCAN {
-    fn fuzzy_dbscan<P: MetricSpace>(&self, points: &[P]) -> Vec<Cluster> {
-        let mut clusters = Vec::new();
-        let mut noise_cluster = Vec::new();
-        let mut visited = vec![false; points.len()];
-        for point_index in 0..points.len() {
-            if visited[point_index] {
+    fn step_with<P: MetricSpace>(
+        &self,
+        points: &[P],
+        state: &mut ClusterState,
+        max_points: usize,
+        neighborhoods: &Neighborhoods,
+    ) -> bool {
+        let mut budget = max_points;
+        while budget > 0 {
+            if let Some(expansion) = &mut state.expansion {
+                if let Some(neighbor_index) = expansion.queue.pop_front() {
+                    state.visited.visit(neighbor_index);
+                    self.expand_cluster_fuzzy(
+                        points,
+                        expansion,
+                        neighbor_index,
+                        neighborhoods,
+                        &state.non_core,
+                        &state.constraints,
+                    );
+                    budget -= 1;
+                } else if let Some(expansion) = state.expansion.take() {
+                    let cluster = self.finish_cluster(points, expansion);
+                    if !state.constraints.is_empty() {
+                        // later clusters must not expand through this one, or cannot-links would only move the
+                        // blocked point into a copy of the cluster
+                        for assignment in &cluster {
+                            if assignment.category == Category::Core {
+                                state.non_core[assignment.index] = true;
+                            }
+                        }
+                    }
+                    state.clusters.push(cluster);
+                }
+                continue;
+            }
+
+            if let Some(seeds) = state.seeds.pop_front() {
+                budget = budget.saturating_sub(seeds.len());
+                for &seed in &seeds {
+                    state.visited.visit(seed);
+                }
+                state.expansion = Some(self.seed_expansion(
+                    points,
+                    &seeds,
+                    neighborhoods,
+                    &state.non_core,
+                    &state.constraints,
+                ));
+                continue;
+            }
+
+            let point_index = state.next_point;
+            if point_index >= points.len() {
+                return true;
+            }
+            state.next_point += 1;
+            if state.visited.is_visited(point_index) {
                 continue;
             }
-            visited[point_index] = true;
-            let neighbor_indices = self.region_query(points, point_index);
-            let point_label = self.mu_min_p(self.density(point_index, &neighbor_indices, points));
+            state.visited.visit(point_index);
+            budget -= 1;
+            let neighbor_indices = self.region_query(points, point_index, neighborhoods);
+            let point_label =
+                self.core_label(points, point_index, &neighbor_indices, &state.non_core);
             if point_label == 0.0 {
-                noise_cluster.push(Assignment {
+                state.noise.push(Assignment {
                     index: point_index,
                     category: Category::Noise,
                     label: 1.0,
+                    core_distance: None,
+                    core_index: None,
                 });
             } else {
-                clusters.push(self.expand_cluster_fuzzy(
-                    point_label,
+                state.expansion = Some(Expansion::new(
                     point_index,
+                    point_label,
                     neighbor_indices,
-                    points,
-                    &mut visited,
+                    points.len(),
+                    &state.constraints,
                 ));
             }
         }
-        if !noise_cluster.is_empty() {
-            clusters.push(noise_cluster);
-        }
-        clusters
+        state.is_done()
     }
 
-    fn expand_cluster_fuzzy<P: MetricSpace>(
+    /// Starts an expansion that contains all `seeds`: those dense enough are core points whose neighbors are
+    /// expanded, the others join as border points.
+    fn seed_expansion<P: MetricSpace>(
         &self,
-        point_label: f64,
-        point_index: usize,
-        mut neighbor_indices: HashSet<usize>,
         points: &[P],
-        visited: &mut [bool],
-    ) -> Vec<Assignment> {
-        let mut cluster = vec![Assignment {
-            index: point_index,
-            category: Category::Core,
-            label: point_label,
-        }];
-        let mut border_points = Vec::new();
-        let mut neighbor_visited = vec![false; points.len()];
-        while let Some(neighbor_index) = take_arbitrary(&mut neighbor_indices) {
-            neighbor_visited[neighbor_index] = true;
-            visited[neighbor_index] = true;
-            let neighbor_neighbor_indices = self.region_query(points, neighbor_index);
-            let neighbor_label =
-                self.mu_min_p(self.density(neighbor_index, &neighbor_neighbor_indices, points));
-            if neighbor_label > 0.0 {
-                for neighbor_neighbor_index in neighbor_neighbor_indices {
-                    if !neighbor_visited[neighbor_neighbor_index] {
-                        neighbor_indices.insert(neighbor_neighbor_index);
-                    }
+        seeds: &[usize],
+        neighborhoods: &Neighborhoods,
+        non_core: &[bool],
+        constraints: &Constraints,
+    ) -> Expansion {
+        let mut expansion = Expansion {
+            cluster: Vec::new(),
+            border_points: Vec::new(),
+            queue: VecDeque::new(),
+            queued: vec![false; points.len()],
+        };
+        for &seed in seeds {
+            expansion.queued[seed] = true;
+        }
+        for &seed in seeds {
+            expansion.enqueue_partners(seed, constraints);
+        }
+        for &seed in seeds {
+            let neighbor_indices = self.region_query(points, seed, neighborhoods);
+            let label = self.core_label(points, seed, &neighbor_indices, non_core);
+            if label > 0.0 {
+                for neighbor_index in neighbor_indices {
+                    expansion.enqueue(neighbor_index, constraints);
                 }
-                cluster.push(Assignment {
-                    index: neighbor_index,
+                expansion.cluster.push(Assignment {
+                    index: seed,
                     category: Category::Core,
-                    label: neighbor_label,
+                    label,
+                    core_distance: None,
+                    core_index: None,
                 });
             } else {
-                border_points.push(Assignment {
-                    index: neighbor_index,
+                expansion.border_points.push(Assignment {
+                    index: seed,
                     category: Category::Border,
                     label: f64::MAX,
+                    core_distance: None,
+                    core_index: None,
                 });
             }
         }
+        expansion
+    }
+
+    fn expand_cluster_fuzzy<P: MetricSpace>(
+        &self,
+        points: &[P],
+        expansion: &mut Expansion,
+        neighbor_index: usize,
+        neighborhoods: &Neighborhoods,
+        non_core: &[bool],
+        constraints: &Constraints,
+    ) {
+        let neighbor_neighbor_indices = self.region_query(points, neighbor_index, neighborhoods);
+        let neighbor_label =
+            self.core_label(points, neighbor_index, &neighbor_neighbor_indices, non_core);
+        if neighbor_label > 0.0 {
+            for neighbor_neighbor_index in neighbor_neighbor_indices {
+                expansion.enqueue(neighbor_neighbor_index, constraints);
+            }
+            expansion.cluster.push(Assignment {
+                index: neighbor_index,
+                category: Category::Core,
+                label: neighbor_label,
+                core_distance: None,
+                core_index: None,
+            });
+        } else {
+            expansion.border_points.push(Assignment {
+                index: neighbor_index,
+                category: Category::Border,
+                label: f64::MAX,
+                core_distance: None,
+                core_index: None,
+            });
+        }
+    }
+
+    fn finish_cluster<P: MetricSpace>(&self, points: &[P], expansion: Expansion) -> Cluster {
+        let Expansion {
+            mut cluster,
+            mut border_points,
+            ..
+        } = expansion;
         for border_point in &mut border_points {
             for cluster_point in &cluster {
-                let mu_distance =
-                    self.mu_distance(&points[border_point.index], &points[cluster_point.index]);
+                let distance = points[border_point.index].distance(&points[cluster_point.index]);
+                let mu_distance = self.mu(distance);
                 if mu_distance > 0.0 {
-                    border_point.label =
-                        cluster_point.label.min(mu_distance).min(border_point.label);
+                    let label = cluster_point.label.min(mu_distance);
+                    if label < border_point.label {
+                        border_point.label = label;
+                        border_point.core_distance = Some(distance);
+                        border_point.core_index = Some(cluster_point.index);
+                    }
                 }
             }
         }
+        for border_point in &mut border_points {
+            // seeds far from the cluster's core points, or points exactly at eps_max, have no label yet
+            if border_point.label == f64::MAX {
+                border_point.label = 0.0;
+            }
+        }
         cluster.append(&mut border_points);
         cluster
     }
 
-    fn region_query<P: MetricSpace>(&self, points: &[P], point_index: usize) -> HashSet<usize> {
-        points
-            .iter()
-            .enumerate()
-            .filter(|(neighbor_index, neighbor_point)| {
-                *neighbor_index != point_index
-                    && neighbor_point.distance(&points[point_index]) <= self.eps_max
-            }).map(|(neighbor_index, _)| neighbor_index)
-            .collect() //TODO: would be neat to prevent this allocation.
+    fn region_query<P: MetricSpace>(
+        &self,
+        points: &[P],
+        point_index: usize,
+        neighborhoods: &Neighborhoods,
+    ) -> Vec<usize> {
+        match neighborhoods {
+            Neighborhoods::Lists(lists) => lists[point_index]
+                .iter()
+                .copied()
+                .filter(|&neighbor_index| {
+                    points[neighbor_index]
+                        .distance_within(&points[point_index], self.eps_max)
+                        .is_some()
+                })
+                .collect(),
+            _ => neighborhoods.search(
+                points,
+                &points[point_index],
+                self.eps_max,
+                Some(point_index),
+            ),
+        }
+    }
+
+    /// Returns the label of the point at `point_index` as a core point, or 0 if it isn't one: either its density
+    /// is too low or it is flagged in `non_core`.
+    fn core_label<P: MetricSpace>(
+        &self,
+        points: &[P],
+        point_index: usize,
+        neighbor_indices: &[usize],
+        non_core: &[bool],
+    ) -> f64 {
+        if non_core[point_index] {
+            return 0.0;
+        }
+        self.mu_min_p(self.density(point_index, neighbor_indices, points))
     }
 
     fn density<P: MetricSpace>(
         &self,
         point_index: usize,
-        neighbor_indices: &HashSet<usize>,
+        neighbor_indices: &[usize],
         points: &[P],
     ) -> f64 {
-        1.0 + neighbor_indices.iter().fold(0.0, |sum, &neighbor_index| {
-            sum + self.mu_distance(&points[point_index], &points[neighbor_index])
-        })
+        points[point_index].weight()
+            + neighbor_indices.iter().fold(0.0, |sum, &neighbor_index| {
+                let neighbor = &points[neighbor_index];
+                sum + neighbor.weight() * self.mu_distance(&points[point_index], neighbor)
+            })
     }
 
     fn mu_min_p(&self, n: f64) -> f64 {
@@ -234,13 +827,1395 @@ impl FuzzyDBSCAN {
     }
 
     fn mu_distance<P: MetricSpace>(&self, a: &P, b: &P) -> f64 {
-        let distance = a.distance(b);
+        self.mu(a.distance(b))
+    }
+
+    fn mu(&self, distance: f64) -> f64 {
         if distance <= self.eps_min {
             1.0
         } else if distance > self.eps_max {
             0.0
+        } else if self.eps_max == f64::INFINITY {
+            // the limit of the linear falloff, which would compute infinity over infinity
+            1.0
         } else {
             (self.eps_max - distance) / (self.eps_max - self.eps_min)
         }
     }
 }
+
+/// The outcome of [`sweep_eps`](FuzzyDBSCAN::sweep_eps).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sweep {
+    /// The outcome of each level, in the order the levels were given.
+    pub levels: Vec<SweepLevel>,
+    /// For each point, the index of the level with the smallest `eps_max` at which it belongs to a cluster, or
+    /// `None` if it is noise at every level.
+    pub join_levels: Vec<Option<usize>>,
+}
+
+/// The outcome of one level of [`sweep_eps`](FuzzyDBSCAN::sweep_eps).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SweepLevel {
+    /// Number of clusters, not counting the noise group.
+    pub cluster_count: usize,
+    /// Number of points that belong to no cluster.
+    pub noise_count: usize,
+}
+
+/// Merges the sets of `a` and `b` in the union-find `parents` and returns whether they were different sets.
+fn union(parents: &mut [usize], a: usize, b: usize) -> bool {
+    let (a, b) = (find(parents, a), find(parents, b));
+    if a == b {
+        return false;
+    }
+    parents[a.max(b)] = a.min(b);
+    true
+}
+
+/// Returns the representative of the set of `index` in the union-find `parents`, halving paths on the way.
+fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+/// A structure for finding the neighbors of points that depends only on the points, not on clustering parameters,
+/// so it can be built once and reused for clusterings of the same points with different parameters.
+///
+/// When every point has a [line position](MetricSpace::line_position), the index holds the points sorted by it.
+/// Otherwise, when every point has [coordinates](MetricSpace::coordinates), it holds a k-d tree of them, which
+/// answers queries of any radius. Otherwise it holds nothing and neighborhood queries compare each point with all
+/// others. Points that [move](SpatialIndex::move_points) keep their old place in the index and are compared with
+/// each query separately, until so many have moved that rebuilding the index is cheaper.
+#[derive(Clone)]
+pub struct SpatialIndex(Neighborhoods);
+
+impl SpatialIndex {
+    pub fn new<P: MetricSpace>(points: &[P]) -> Self {
+        SpatialIndex(Neighborhoods::new(points))
+    }
+
+    /// Estimates the most bytes an index of `point_count` points with `dimensions` coordinates holds, which is
+    /// the size of a k-d tree of them.
+    pub fn estimated_memory(point_count: usize, dimensions: usize) -> usize {
+        let coordinates = dimensions * std::mem::size_of::<f64>();
+        // the order and split dimension of each node, or the order and position of each point of a line
+        let nodes = 2 * std::mem::size_of::<usize>();
+        point_count * (coordinates + nodes)
+    }
+
+    /// Whether the index speeds up neighborhood queries rather than comparing each point with all others.
+    pub fn is_accelerated(&self) -> bool {
+        !matches!(self.0, Neighborhoods::Scan)
+    }
+
+    /// Returns the indices of the points within `eps` of the point at `point_index` (excluding itself), sorted by
+    /// index. `points` must be the indexed points.
+    pub fn neighbors<P: MetricSpace>(
+        &self,
+        points: &[P],
+        point_index: usize,
+        eps: f64,
+    ) -> Vec<usize> {
+        let radius = FuzzyDBSCAN {
+            eps_min: eps,
+            eps_max: eps,
+            pts_min: 1.0,
+            pts_max: 1.0,
+        };
+        radius.region_query(points, point_index, &self.0)
+    }
+
+    /// Updates the index after the points at `changed` moved to their positions in `points`, which must be the
+    /// indexed points with the moves applied.
+    ///
+    /// The moved points are compared with every query until more than the square root of the point count have
+    /// moved, or a moved point can't be indexed like the others; then the index is built again.
+    pub fn move_points<P: MetricSpace>(&mut self, points: &[P], changed: &[usize]) {
+        let (moved, movable) = match &mut self.0 {
+            Neighborhoods::Scan => return,
+            Neighborhoods::Line { moved, .. } => {
+                let movable = changed.iter().all(|&point_index| {
+                    points[point_index]
+                        .line_position()
+                        .is_some_and(|position| position.is_finite())
+                });
+                (moved, movable)
+            }
+            Neighborhoods::Tree { tree, moved } => {
+                let movable = changed
+                    .iter()
+                    .all(|&point_index| tree.center(&points[point_index]).is_some());
+                (moved, movable)
+            }
+            Neighborhoods::Lists(_) => {
+                unreachable!("a spatial index never holds neighborhood lists")
+            }
+        };
+        moved.extend_from_slice(changed);
+        moved.sort_unstable();
+        moved.dedup();
+        // every search compares the moved points one by one
+        if movable && moved.len().saturating_mul(moved.len()) <= points.len() {
+            return;
+        }
+        *self = SpatialIndex::new(points);
+    }
+}
+
+/// How neighborhood queries find the points within `eps_max`.
+#[derive(Clone)]
+enum Neighborhoods {
+    /// Compare the point with every other point.
+    Scan,
+    /// Look up the points sorted by [line position](MetricSpace::line_position) around the position of the point.
+    Line {
+        /// Point indices by position.
+        order: Vec<usize>,
+        /// The position of each point of `order`, as indexed.
+        positions: Vec<f64>,
+        /// Sorted indices of the points that moved since, whose entries are stale.
+        moved: Vec<usize>,
+    },
+    /// Descend into the parts of a k-d tree of the points that are within `eps_max` of the point.
+    Tree {
+        tree: KdTree,
+        /// Sorted indices of the points that moved since, whose nodes are stale.
+        moved: Vec<usize>,
+    },
+    /// Filter the neighbors of each point within a larger radius, sorted by index.
+    Lists(Vec<Vec<usize>>),
+}
+
+impl Neighborhoods {
+    fn new<P: MetricSpace>(points: &[P]) -> Self {
+        Self::line(points)
+            .or_else(|| {
+                KdTree::new(points).map(|tree| Neighborhoods::Tree {
+                    tree,
+                    moved: Vec::new(),
+                })
+            })
+            .unwrap_or(Neighborhoods::Scan)
+    }
+
+    /// Sorts the points by line position, if they all have a finite one.
+    fn line<P: MetricSpace>(points: &[P]) -> Option<Self> {
+        // infinite positions can be at distance NaN from each other, which no search range can hold
+        let positions: Option<Vec<f64>> = points
+            .iter()
+            .map(|point| {
+                point
+                    .line_position()
+                    .filter(|position| position.is_finite())
+            })
+            .collect();
+        let positions = positions.filter(|_| points.len() > 1)?;
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|&a, &b| positions[a].total_cmp(&positions[b]));
+        let positions = order
+            .iter()
+            .map(|&point_index| positions[point_index])
+            .collect();
+        Some(Neighborhoods::Line {
+            order,
+            positions,
+            moved: Vec::new(),
+        })
+    }
+
+    /// Returns the indices of the `points` within `radius` of `center` (except `excluded`), sorted by index.
+    ///
+    /// Searches like a region query of [`Neighborhoods::Scan`] if `center` can't be looked up in the index or the
+    /// index only holds neighborhood lists.
+    fn search<P: MetricSpace>(
+        &self,
+        points: &[P],
+        center: &P,
+        radius: f64,
+        excluded: Option<usize>,
+    ) -> Vec<usize> {
+        let within = |point_index: usize| {
+            Some(point_index) != excluded
+                && points[point_index]
+                    .distance_within(center, radius)
+                    .is_some()
+        };
+        let slack = radius * (1. + SEARCH_TOLERANCE);
+        let mut candidates = Vec::new();
+        let moved = match self {
+            Neighborhoods::Line {
+                order,
+                positions,
+                moved,
+            } => match center
+                .line_position()
+                .filter(|position| position.is_finite())
+            {
+                Some(position) => {
+                    let start = positions.partition_point(|&other| other < position - slack);
+                    let end = positions.partition_point(|&other| other <= position + slack);
+                    candidates.extend_from_slice(&order[start..end]);
+                    moved
+                }
+                None => return (0..points.len()).filter(|&i| within(i)).collect(),
+            },
+            Neighborhoods::Tree { tree, moved } => match tree.center(center) {
+                Some(coordinates) => {
+                    tree.visit_near(&coordinates, slack, |point_index| {
+                        candidates.push(point_index)
+                    });
+                    moved
+                }
+                None => return (0..points.len()).filter(|&i| within(i)).collect(),
+            },
+            Neighborhoods::Scan | Neighborhoods::Lists(_) => {
+                return (0..points.len()).filter(|&i| within(i)).collect();
+            }
+        };
+        let mut neighbor_indices: Vec<usize> = candidates
+            .into_iter()
+            .filter(|point_index| moved.binary_search(point_index).is_err())
+            .chain(moved.iter().copied())
+            .filter(|&point_index| within(point_index))
+            .collect();
+        // a scan lists neighbors by index, which decides the order in which clusters are expanded
+        neighbor_indices.sort_unstable();
+        neighbor_indices
+    }
+}
+
+/// Relative tolerance by which index searches widen their radius. Distances of `f32` coordinates are computed
+/// from rounded differences, which can be slightly smaller than the exact differences the index compares.
+const SEARCH_TOLERANCE: f64 = 1e-6;
+
+/// A static k-d tree of points with [coordinates](MetricSpace::coordinates).
+///
+/// The tree is implicit in `order`: the point at the middle of a range of `order` splits it along `axes` of the
+/// same position, with the points of the first half at most and those of the second half at least its coordinate.
+#[derive(Clone)]
+struct KdTree {
+    /// Point indices in tree order.
+    order: Vec<usize>,
+    /// The split dimension of the node at each position of `order`.
+    axes: Vec<usize>,
+    /// Packed coordinates of the points, by point index.
+    coordinates: Vec<f64>,
+    dimensions: usize,
+}
+
+impl KdTree {
+    /// Builds a tree of the points, if they all have finite coordinates with the same number of dimensions.
+    fn new<P: MetricSpace>(points: &[P]) -> Option<Self> {
+        if points.len() < 2 {
+            return None;
+        }
+        let dimensions = points[0].coordinates()?.len();
+        if dimensions == 0 {
+            return None;
+        }
+        let mut coordinates = Vec::with_capacity(points.len() * dimensions);
+        for point in points {
+            let point_coordinates = point.coordinates()?;
+            if point_coordinates.len() != dimensions
+                || !point_coordinates.iter().all(|value| value.is_finite())
+            {
+                return None;
+            }
+            coordinates.extend(point_coordinates);
+        }
+        let mut tree = KdTree {
+            order: (0..points.len()).collect(),
+            axes: vec![0; points.len()],
+            coordinates,
+            dimensions,
+        };
+        tree.split(0, points.len());
+        Some(tree)
+    }
+
+    fn coordinate(&self, point_index: usize, axis: usize) -> f64 {
+        self.coordinates[point_index * self.dimensions + axis]
+    }
+
+    /// Returns the coordinates of `point` if they are finite with the dimensions of the tree.
+    fn center<P: MetricSpace>(&self, point: &P) -> Option<Vec<f64>> {
+        point.coordinates().filter(|coordinates| {
+            coordinates.len() == self.dimensions
+                && coordinates.iter().all(|value| value.is_finite())
+        })
+    }
+
+    /// Arranges `order[start..end]` as a subtree, split along the dimension in which its points spread most.
+    fn split(&mut self, start: usize, end: usize) {
+        if end - start < 2 {
+            return;
+        }
+        let axis = (0..self.dimensions)
+            .map(|axis| {
+                let (min, max) = self.order[start..end].iter().fold(
+                    (f64::INFINITY, f64::NEG_INFINITY),
+                    |(min, max), &point_index| {
+                        let value = self.coordinate(point_index, axis);
+                        (min.min(value), max.max(value))
+                    },
+                );
+                (axis, max - min)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(axis, _)| axis);
+        let middle = start + (end - start) / 2;
+        let (coordinates, dimensions) = (&self.coordinates, self.dimensions);
+        self.order[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+            coordinates[a * dimensions + axis].total_cmp(&coordinates[b * dimensions + axis])
+        });
+        self.axes[middle] = axis;
+        self.split(start, middle);
+        self.split(middle + 1, end);
+    }
+
+    /// Calls `visit` with every point whose coordinates are within `radius` of `center` along each dimension, and
+    /// possibly some more.
+    fn visit_near(&self, center: &[f64], radius: f64, mut visit: impl FnMut(usize)) {
+        let mut ranges = vec![(0, self.order.len())];
+        while let Some((start, end)) = ranges.pop() {
+            if start >= end {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            let node = self.order[middle];
+            let axis = self.axes[middle];
+            let offset = center[axis] - self.coordinate(node, axis);
+            if offset <= radius {
+                ranges.push((start, middle));
+            }
+            if offset >= -radius {
+                ranges.push((middle + 1, end));
+            }
+            if offset.abs() <= radius {
+                visit(node);
+            }
+        }
+    }
+}
+
+/// Progress of a clustering that is advanced with [`FuzzyDBSCAN::step`].
+///
+/// The state can be serialized with [`to_bytes`](ClusterState::to_bytes) and restored with
+/// [`from_bytes`](ClusterState::from_bytes), e.g. to continue the clustering in another worker. It doesn't
+/// contain the points or parameters, which must be the same when resuming.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterState {
+    visited: Visited,
+    /// The next point to consider as the seed of a cluster.
+    next_point: usize,
+    clusters: Vec<Cluster>,
+    noise: Cluster,
+    /// The cluster that is currently being expanded.
+    expansion: Option<Expansion>,
+    /// Groups of seed points whose clusters haven't been expanded yet.
+    seeds: VecDeque<Vec<usize>>,
+    /// Points that may not become core points: those barred with [`exclude_cores`](ClusterState::exclude_cores)
+    /// and, with constraints, the core points of finished clusters.
+    non_core: Vec<bool>,
+    constraints: Constraints,
+}
+
+/// Must-link and cannot-link constraints between points, see [`ClusterState::constrain`].
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Constraints {
+    must_link: Vec<(usize, usize)>,
+    cannot_link: Vec<(usize, usize)>,
+    /// Must-link groups, sorted by point index.
+    groups: Vec<Vec<usize>>,
+    /// The index in `groups` of each must-linked point.
+    group_of: HashMap<usize, usize>,
+    /// The points each constrained point cannot be linked with.
+    partners: HashMap<usize, Vec<usize>>,
+}
+
+impl Constraints {
+    fn new(must_link: &[(usize, usize)], cannot_link: &[(usize, usize)]) -> Self {
+        // union-find over the constrained points
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+        fn root(parents: &mut HashMap<usize, usize>, index: usize) -> usize {
+            let parent = *parents.entry(index).or_insert(index);
+            if parent == index {
+                return index;
+            }
+            let root = root(parents, parent);
+            parents.insert(index, root);
+            root
+        }
+        for &(a, b) in must_link {
+            let (a, b) = (root(&mut parents, a), root(&mut parents, b));
+            parents.insert(a.max(b), a.min(b));
+        }
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        let indices: Vec<usize> = parents.keys().copied().collect();
+        for index in indices {
+            let group = root(&mut parents, index);
+            members.entry(group).or_default().push(index);
+        }
+        let mut groups = Vec::with_capacity(members.len());
+        let mut group_of = HashMap::new();
+        for mut group in members.into_values() {
+            group.sort_unstable();
+            for &index in &group {
+                group_of.insert(index, groups.len());
+            }
+            groups.push(group);
+        }
+        let mut partners: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &(a, b) in cannot_link {
+            partners.entry(a).or_default().push(b);
+            partners.entry(b).or_default().push(a);
+        }
+        Constraints {
+            must_link: must_link.to_vec(),
+            cannot_link: cannot_link.to_vec(),
+            groups,
+            group_of,
+            partners,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.must_link.is_empty() && self.cannot_link.is_empty()
+    }
+
+    /// Returns the must-link group of the point at `index`, if it has one.
+    fn group(&self, index: usize) -> Option<&[usize]> {
+        self.group_of
+            .get(&index)
+            .map(|&group| &self.groups[group][..])
+    }
+
+    /// Returns whether the point at `index` cannot be linked with a point that is `queued`.
+    fn conflicts(&self, index: usize, queued: &[bool]) -> bool {
+        self.partners
+            .get(&index)
+            .is_some_and(|partners| partners.iter().any(|&partner| queued[partner]))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Visited {
+    flags: Vec<bool>,
+    count: usize,
+}
+
+impl Visited {
+    fn is_visited(&self, index: usize) -> bool {
+        self.flags[index]
+    }
+
+    fn visit(&mut self, index: usize) {
+        if !self.flags[index] {
+            self.flags[index] = true;
+            self.count += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Expansion {
+    cluster: Vec<Assignment>,
+    border_points: Vec<Assignment>,
+    /// Neighbors that still have to be expanded, in the order they were found.
+    queue: VecDeque<usize>,
+    /// Whether a point has ever been queued in this expansion.
+    queued: Vec<bool>,
+}
+
+impl Expansion {
+    fn new(
+        point_index: usize,
+        point_label: f64,
+        neighbor_indices: Vec<usize>,
+        point_count: usize,
+        constraints: &Constraints,
+    ) -> Self {
+        let mut expansion = Expansion {
+            cluster: vec![Assignment {
+                index: point_index,
+                category: Category::Core,
+                label: point_label,
+                core_distance: None,
+                core_index: None,
+            }],
+            border_points: Vec::new(),
+            queue: VecDeque::with_capacity(neighbor_indices.len()),
+            queued: vec![false; point_count],
+        };
+        // the point itself is already in the cluster and must not be expanded again from a neighbor
+        expansion.queued[point_index] = true;
+        expansion.enqueue_partners(point_index, constraints);
+        for neighbor_index in neighbor_indices {
+            expansion.enqueue(neighbor_index, constraints);
+        }
+        expansion
+    }
+
+    /// Queues the point at `index` together with the rest of its must-link group, unless it has been queued
+    /// before or a point of the group cannot be linked with a queued point.
+    fn enqueue(&mut self, index: usize, constraints: &Constraints) {
+        if self.queued[index] {
+            return;
+        }
+        let group = constraints
+            .group(index)
+            .unwrap_or(std::slice::from_ref(&index));
+        if group
+            .iter()
+            .any(|&member| constraints.conflicts(member, &self.queued))
+        {
+            return;
+        }
+        for &member in group {
+            if !self.queued[member] {
+                self.queued[member] = true;
+                self.queue.push_back(member);
+            }
+        }
+    }
+
+    /// Queues the must-link group of the point at `index`, which is already in the cluster.
+    fn enqueue_partners(&mut self, index: usize, constraints: &Constraints) {
+        for &member in constraints.group(index).unwrap_or_default() {
+            self.enqueue(member, constraints);
+        }
+    }
+}
+
+impl ClusterState {
+    /// Creates the state of a clustering of `point_count` points that hasn't started yet.
+    pub fn new(point_count: usize) -> Self {
+        ClusterState {
+            visited: Visited {
+                flags: vec![false; point_count],
+                count: 0,
+            },
+            next_point: 0,
+            clusters: Vec::new(),
+            noise: Vec::new(),
+            expansion: None,
+            seeds: VecDeque::new(),
+            non_core: vec![false; point_count],
+            constraints: Constraints::default(),
+        }
+    }
+
+    /// Creates the state of a clustering of `point_count` points that first grows a cluster from each set of seed
+    /// points, given as `(point index, hint)` pairs, e.g. points that are known to belong together.
+    ///
+    /// Seeds with the same hint start one cluster, and these clusters come first, ordered by hint. Hints take
+    /// precedence over density in these ways:
+    /// - every seed is a member of its hint's cluster. Seeds that are core points by density are expanded as
+    ///   usual; the others are border points, with label 0 if no core point of the cluster is within `eps_max`.
+    /// - seeds with different hints are never merged. If their regions are density-connected, the points in
+    ///   between belong to several clusters.
+    ///
+    /// The remaining points are clustered as usual afterwards.
+    ///
+    /// # Panics
+    /// Panics if a seed's point index is not below `point_count`.
+    pub fn with_seeds(point_count: usize, seeds: &[(usize, u32)]) -> Self {
+        let mut state = Self::new(point_count);
+        let mut groups: Vec<(u32, Vec<usize>)> = Vec::new();
+        for &(index, hint) in seeds {
+            assert!(index < point_count, "seed index {} out of range", index);
+            match groups.iter_mut().find(|(other, _)| *other == hint) {
+                Some((_, group)) if !group.contains(&index) => group.push(index),
+                Some(_) => {}
+                None => groups.push((hint, vec![index])),
+            }
+        }
+        groups.sort_by_key(|(hint, _)| *hint);
+        state.seeds = groups.into_iter().map(|(_, group)| group).collect();
+        state
+    }
+
+    /// Bars the points at `indices` from becoming core points, e.g. known artifacts that shouldn't anchor clusters.
+    ///
+    /// Only the core point test changes: an excluded point never starts or expands a cluster, however dense its
+    /// neighborhood is, but it still counts towards the density of its neighbors and joins the clusters of core
+    /// points within `eps_max` as a border point. Excluded points without such a core point are noise, and
+    /// excluded seeds join their hint's cluster as border points.
+    ///
+    /// Must be called before the clustering is stepped.
+    ///
+    /// # Panics
+    /// Panics if an index is not below the point count.
+    pub fn exclude_cores(&mut self, indices: &[usize]) {
+        let point_count = self.point_count();
+        for &index in indices {
+            assert!(index < point_count, "excluded index {} out of range", index);
+            self.non_core[index] = true;
+        }
+    }
+
+    /// Constrains which points may share a cluster, e.g. to incorporate feedback from users. Replaces earlier
+    /// constraints.
+    ///
+    /// - Points of a `must_link` pair are forced together, transitively: whenever a cluster reaches a point, its
+    ///   whole must-link group joins the cluster, and members that aren't dense enough to be core points become
+    ///   border points (with label 0 if no core point of the cluster is within `eps_max`).
+    /// - Points of a `cannot_link` pair are never in the same cluster: an expansion doesn't add a point (or its
+    ///   must-link group) if it cannot be linked with a point already in the cluster. The blocked point can
+    ///   start or join a later cluster, but later clusters don't expand through the core points of earlier ones,
+    ///   which only join them as border points, so the cluster doesn't grow back.
+    ///
+    /// Cannot-links between points of the same must-link group can't be satisfied, and the group is only kept
+    /// together as far as cannot-links allow. Points in several clusters as border points can still end up in the
+    /// same cluster as a cannot-linked point if neither was expanded. Must be called before the clustering is
+    /// stepped.
+    ///
+    /// # Panics
+    /// Panics if an index is not below the point count.
+    pub fn constrain(&mut self, must_link: &[(usize, usize)], cannot_link: &[(usize, usize)]) {
+        let point_count = self.point_count();
+        for &(a, b) in must_link.iter().chain(cannot_link) {
+            assert!(
+                a < point_count && b < point_count,
+                "constrained pair ({}, {}) out of range",
+                a,
+                b
+            );
+        }
+        self.constraints = Constraints::new(must_link, cannot_link);
+    }
+
+    /// Estimates the peak number of bytes a clustering of `point_count` points uses besides the points themselves.
+    ///
+    /// Neighborhoods are computed on demand, so memory grows linearly with the point count: the visited and queued
+    /// flags, the queue of the current expansion, one neighborhood and the assignments of all clusters (assuming
+    /// each point is assigned about once). Points that belong to many overlapping clusters can use more. The
+    /// [`SpatialIndex`] that finds the neighborhoods (see [`SpatialIndex::estimated_memory`]) and the
+    /// neighborhoods that [`cluster_levels`](FuzzyDBSCAN::cluster_levels) caches to share them between several
+    /// levels are not included.
+    pub fn estimated_memory(point_count: usize) -> usize {
+        let flags = 2 * point_count;
+        let queue = point_count * std::mem::size_of::<usize>();
+        let neighborhood = point_count * std::mem::size_of::<usize>();
+        let assignments = point_count * std::mem::size_of::<Assignment>();
+        flags + queue + neighborhood + assignments
+    }
+
+    /// Returns the number of points being clustered.
+    pub fn point_count(&self) -> usize {
+        self.visited.flags.len()
+    }
+
+    /// Returns whether every point has been processed.
+    pub fn is_done(&self) -> bool {
+        self.next_point >= self.point_count() && self.expansion.is_none() && self.seeds.is_empty()
+    }
+
+    /// Returns the fraction of points that have been visited, between `0.0` and `1.0`.
+    pub fn progress(&self) -> f64 {
+        if self.point_count() == 0 {
+            1.0
+        } else {
+            self.visited.count as f64 / self.point_count() as f64
+        }
+    }
+
+    /// Returns the clusters, followed by the noise points if there are any.
+    ///
+    /// A point that was too sparse to start a cluster but later became a border point of one is not noise. If the
+    /// clustering isn't [done](ClusterState::is_done), these are only the clusters completed so far.
+    pub fn into_clusters(self) -> Vec<Cluster> {
+        let mut clusters = self.clusters;
+        let mut clustered = vec![false; self.visited.flags.len()];
+        for assignment in clusters.iter().flatten() {
+            clustered[assignment.index] = true;
+        }
+        let mut noise = self.noise;
+        noise.retain(|assignment| !clustered[assignment.index]);
+        if !noise.is_empty() {
+            clusters.push(noise);
+        }
+        clusters
+    }
+}
+
+/// Version of the format written by [`ClusterState::to_bytes`].
+const STATE_VERSION: u32 = 1;
+
+impl ClusterState {
+    /// Serializes the state into a little-endian byte buffer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter(Vec::new());
+        writer.u32(STATE_VERSION);
+        writer.u32(self.point_count() as u32);
+        writer.flags(&self.visited.flags);
+        writer.u32(self.next_point as u32);
+        writer.u32(self.clusters.len() as u32);
+        for cluster in &self.clusters {
+            writer.assignments(cluster);
+        }
+        writer.assignments(&self.noise);
+        match &self.expansion {
+            None => writer.0.push(0),
+            Some(expansion) => {
+                writer.0.push(1);
+                writer.assignments(&expansion.cluster);
+                writer.assignments(&expansion.border_points);
+                writer.u32(expansion.queue.len() as u32);
+                for &index in &expansion.queue {
+                    writer.u32(index as u32);
+                }
+                writer.flags(&expansion.queued);
+            }
+        }
+        writer.u32(self.seeds.len() as u32);
+        for group in &self.seeds {
+            writer.u32(group.len() as u32);
+            for &index in group {
+                writer.u32(index as u32);
+            }
+        }
+        writer.flags(&self.non_core);
+        for pairs in [&self.constraints.must_link, &self.constraints.cannot_link] {
+            writer.u32(pairs.len() as u32);
+            for &(a, b) in pairs {
+                writer.u32(a as u32);
+                writer.u32(b as u32);
+            }
+        }
+        writer.0
+    }
+
+    /// Restores a state serialized with [`to_bytes`](ClusterState::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClusterError> {
+        let mut reader = StateReader(bytes);
+        let version = reader.u32()?;
+        if version != STATE_VERSION {
+            return Err(ClusterError::InvalidState);
+        }
+        let point_count = reader.u32()? as usize;
+        let flags = reader.flags(point_count)?;
+        let next_point = reader.u32()? as usize;
+        let cluster_count = reader.u32()? as usize;
+        let clusters = (0..cluster_count)
+            .map(|_| reader.assignments(point_count))
+            .collect::<Result<_, _>>()?;
+        let noise = reader.assignments(point_count)?;
+        let expansion = match reader.u8()? {
+            0 => None,
+            1 => {
+                let cluster = reader.assignments(point_count)?;
+                let border_points = reader.assignments(point_count)?;
+                let queue_len = reader.u32()? as usize;
+                let queue = (0..queue_len)
+                    .map(|_| reader.index(point_count))
+                    .collect::<Result<_, _>>()?;
+                let queued = reader.flags(point_count)?;
+                Some(Expansion {
+                    cluster,
+                    border_points,
+                    queue,
+                    queued,
+                })
+            }
+            _ => return Err(ClusterError::InvalidState),
+        };
+        let seed_group_count = reader.u32()?;
+        let seeds = (0..seed_group_count)
+            .map(|_| {
+                let len = reader.u32()? as usize;
+                (0..len).map(|_| reader.index(point_count)).collect()
+            })
+            .collect::<Result<_, _>>()?;
+        let non_core = reader.flags(point_count)?;
+        let mut pairs = || -> Result<Vec<(usize, usize)>, ClusterError> {
+            let len = reader.u32()?;
+            (0..len)
+                .map(|_| Ok((reader.index(point_count)?, reader.index(point_count)?)))
+                .collect()
+        };
+        let must_link = pairs()?;
+        let cannot_link = pairs()?;
+        if !reader.0.is_empty() || next_point > point_count {
+            return Err(ClusterError::InvalidState);
+        }
+        Ok(ClusterState {
+            visited: Visited {
+                count: flags.iter().filter(|&&flag| flag).count(),
+                flags,
+            },
+            next_point,
+            clusters,
+            noise,
+            expansion,
+            seeds,
+            non_core,
+            constraints: Constraints::new(&must_link, &cannot_link),
+        })
+    }
+}
+
+struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn flags(&mut self, flags: &[bool]) {
+        self.0.extend(flags.iter().map(|&flag| flag as u8));
+    }
+
+    fn assignments(&mut self, assignments: &[Assignment]) {
+        self.u32(assignments.len() as u32);
+        for assignment in assignments {
+            self.u32(assignment.index as u32);
+            self.f64(assignment.label);
+            self.0.push(match assignment.category {
+                Category::Core => 0,
+                Category::Border => 1,
+                Category::Noise => 2,
+                Category::Reassigned => 3,
+            });
+            self.f64(assignment.core_distance.unwrap_or(f64::NAN));
+            self.u32(assignment.core_index.map_or(u32::MAX, |index| index as u32));
+        }
+    }
+}
+
+struct StateReader<'a>(&'a [u8]);
+
+impl StateReader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], ClusterError> {
+        let (bytes, rest) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or(ClusterError::InvalidState)?;
+        self.0 = rest;
+        Ok(*bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ClusterError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ClusterError> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f64(&mut self) -> Result<f64, ClusterError> {
+        Ok(f64::from_le_bytes(self.bytes()?))
+    }
+
+    fn index(&mut self, point_count: usize) -> Result<usize, ClusterError> {
+        let index = self.u32()? as usize;
+        if index < point_count {
+            Ok(index)
+        } else {
+            Err(ClusterError::InvalidState)
+        }
+    }
+
+    fn flags(&mut self, count: usize) -> Result<Vec<bool>, ClusterError> {
+        if self.0.len() < count {
+            return Err(ClusterError::InvalidState);
+        }
+        let (flags, rest) = self.0.split_at(count);
+        self.0 = rest;
+        flags
+            .iter()
+            .map(|&flag| match flag {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(ClusterError::InvalidState),
+            })
+            .collect()
+    }
+
+    fn assignments(&mut self, point_count: usize) -> Result<Vec<Assignment>, ClusterError> {
+        let len = self.u32()? as usize;
+        (0..len)
+            .map(|_| {
+                let index = self.index(point_count)?;
+                let label = self.f64()?;
+                let category = match self.u8()? {
+                    0 => Category::Core,
+                    1 => Category::Border,
+                    2 => Category::Noise,
+                    3 => Category::Reassigned,
+                    _ => return Err(ClusterError::InvalidState),
+                };
+                let core_distance = Some(self.f64()?).filter(|distance| !distance.is_nan());
+                let core_index = match self.u32()? {
+                    u32::MAX => None,
+                    index if (index as usize) < point_count => Some(index as usize),
+                    _ => return Err(ClusterError::InvalidState),
+                };
+                Ok(Assignment {
+                    index,
+                    label,
+                    category,
+                    core_distance,
+                    core_index,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    struct Point(f64, f64);
+
+    impl MetricSpace for Point {
+        fn distance(&self, other: &Self) -> f64 {
+            ((other.0 - self.0).powi(2) + (other.1 - self.1).powi(2)).sqrt()
+        }
+    }
+
+    /// Three rings of points of increasing size plus a few outliers.
+    fn fixture() -> Vec<Point> {
+        let mut points = Vec::new();
+        for (ring, &(cx, cy)) in [(0., 0.), (10., 0.), (0., 10.)].iter().enumerate() {
+            let count = 8 + ring * 6;
+            for i in 0..count {
+                let angle = i as f64 / count as f64 * std::f64::consts::TAU;
+                points.push(Point(cx + angle.cos(), cy + angle.sin()));
+            }
+        }
+        points.extend([Point(5., 5.), Point(20., 20.), Point(-7., 3.)]);
+        points
+    }
+
+    fn dbscan() -> FuzzyDBSCAN {
+        FuzzyDBSCAN {
+            eps_min: 0.6,
+            eps_max: 1.2,
+            pts_min: 2.0,
+            pts_max: 4.0,
+        }
+    }
+
+    #[test]
+    fn resumed_clustering_matches_uninterrupted() {
+        let points = fixture();
+        let dbscan = dbscan();
+        let expected = dbscan.cluster(&points);
+
+        for checkpoint in [1, 5, 12, 30] {
+            let mut state = ClusterState::new(points.len());
+            assert!(!dbscan.step(&points, &mut state, checkpoint));
+            let bytes = state.to_bytes();
+            let mut resumed = ClusterState::from_bytes(&bytes).unwrap();
+            assert_eq!(resumed, state);
+            while !dbscan.step(&points, &mut resumed, 3) {}
+            assert_eq!(resumed.into_clusters(), expected);
+        }
+    }
+
+    /// A 1D point that is clustered by scanning all points.
+    struct Scanned(f64);
+
+    impl MetricSpace for Scanned {
+        fn distance(&self, other: &Self) -> f64 {
+            (other.0 - self.0).abs()
+        }
+    }
+
+    /// A 1D point that is clustered with sorted neighborhoods.
+    struct OnLine(f64);
+
+    impl MetricSpace for OnLine {
+        fn distance(&self, other: &Self) -> f64 {
+            (other.0 - self.0).abs()
+        }
+
+        fn line_position(&self) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn sorted_1d_clustering_matches_scan() {
+        let mut rng = crate::rng::Rng::new(7);
+        let mut values: Vec<f64> = (0..300)
+            .map(|i| (i % 5) as f64 * 20. + rng.gaussian() * if i % 3 == 0 { 4. } else { 1. })
+            .collect();
+        // duplicates and values on the edge of a neighborhood
+        values.extend([0., 0., 1.2, 2.4, 3.6, 200.]);
+        let scanned: Vec<Scanned> = values.iter().map(|&v| Scanned(v)).collect();
+        let on_line: Vec<OnLine> = values.iter().map(|&v| OnLine(v)).collect();
+        let seeds = [(3, 0), (8, 0), (301, 1)];
+        for (eps_min, eps_max, pts_min, pts_max) in [
+            (0.6, 1.2, 2., 4.),
+            (0., 0.5, 3., 3.),
+            (1., 1., 1., 10.),
+            (2., 5., 20., 40.),
+        ] {
+            let dbscan = FuzzyDBSCAN {
+                eps_min,
+                eps_max,
+                pts_min,
+                pts_max,
+            };
+            assert_eq!(dbscan.cluster(&on_line), dbscan.cluster(&scanned));
+            assert_eq!(
+                dbscan.cluster_seeded(&on_line, &seeds),
+                dbscan.cluster_seeded(&scanned, &seeds)
+            );
+            assert_eq!(dbscan.core_labels(&on_line), dbscan.core_labels(&scanned));
+        }
+    }
+
+    /// A 2D point that is clustered with a k-d tree.
+    struct Planar(f64, f64);
+
+    impl MetricSpace for Planar {
+        fn distance(&self, other: &Self) -> f64 {
+            ((other.0 - self.0).powi(2) + (other.1 - self.1).powi(2)).sqrt()
+        }
+
+        fn coordinates(&self) -> Option<Vec<f64>> {
+            Some(vec![self.0, self.1])
+        }
+    }
+
+    #[test]
+    fn tree_clustering_matches_scan() {
+        let mut rng = crate::rng::Rng::new(11);
+        let mut values: Vec<(f64, f64)> = (0..300)
+            .map(|i| {
+                let spread = if i % 3 == 0 { 3. } else { 0.8 };
+                (
+                    (i % 4) as f64 * 10. + rng.gaussian() * spread,
+                    (i % 3) as f64 * 10. + rng.gaussian() * spread,
+                )
+            })
+            .collect();
+        // duplicates and points on the edge of a neighborhood
+        values.extend([(0., 0.), (0., 0.), (1.2, 0.), (0., 2.4), (50., 50.)]);
+        let scanned: Vec<Point> = values.iter().map(|&(x, y)| Point(x, y)).collect();
+        let planar: Vec<Planar> = values.iter().map(|&(x, y)| Planar(x, y)).collect();
+        let index = SpatialIndex::new(&planar);
+        assert!(index.is_accelerated());
+        for eps in [0., 0.3, 1.2, 25., f64::INFINITY] {
+            for point_index in [0, 17, 300, 304] {
+                let expected: Vec<usize> = (0..planar.len())
+                    .filter(|&i| {
+                        i != point_index && planar[i].distance(&planar[point_index]) <= eps
+                    })
+                    .collect();
+                assert_eq!(index.neighbors(&planar, point_index, eps), expected);
+            }
+        }
+        let seeds = [(3, 0), (8, 0), (301, 1)];
+        for (eps_min, eps_max, pts_min, pts_max) in [
+            (0.6, 1.2, 2., 4.),
+            (0., 0.5, 3., 3.),
+            (1., 1., 1., 10.),
+            (2., 5., 20., 40.),
+        ] {
+            let dbscan = FuzzyDBSCAN {
+                eps_min,
+                eps_max,
+                pts_min,
+                pts_max,
+            };
+            assert_eq!(dbscan.cluster(&planar), dbscan.cluster(&scanned));
+            assert_eq!(
+                dbscan.cluster_seeded(&planar, &seeds),
+                dbscan.cluster_seeded(&scanned, &seeds)
+            );
+            assert_eq!(dbscan.core_labels(&planar), dbscan.core_labels(&scanned));
+        }
+    }
+
+    #[test]
+    fn excluded_points_are_never_core_points() {
+        let points = fixture();
+        let dbscan = dbscan();
+        // every ring point is a core point, so excluding one leaves it as a border point of its ring
+        let excluded = [0, 8, 30];
+        let mut state = ClusterState::new(points.len());
+        state.exclude_cores(&excluded);
+        let clusters = FuzzyDBSCAN::cluster_levels(std::slice::from_ref(&dbscan), &points, &state)
+            .pop()
+            .unwrap();
+        assert_eq!(clusters.len(), dbscan.cluster(&points).len());
+        for &index in &excluded {
+            let assignments: Vec<&Assignment> = clusters
+                .iter()
+                .flatten()
+                .filter(|assignment| assignment.index == index)
+                .collect();
+            assert_eq!(assignments.len(), 1);
+            assert_eq!(assignments[0].category, Category::Border);
+            assert!(assignments[0].label > 0.0);
+        }
+
+        // a dense point that is excluded together with all its neighbors can't anchor a cluster
+        let mut state = ClusterState::new(points.len());
+        state.exclude_cores(&(0..8).collect::<Vec<_>>());
+        let clusters = FuzzyDBSCAN::cluster_levels(std::slice::from_ref(&dbscan), &points, &state)
+            .pop()
+            .unwrap();
+        let noise = clusters.last().unwrap();
+        assert!((0..8).all(|index| noise.iter().any(|assignment| assignment.index == index)));
+
+        // exclusions survive serialization
+        state.exclude_cores(&[40]);
+        assert_eq!(ClusterState::from_bytes(&state.to_bytes()).unwrap(), state);
+    }
+
+    #[test]
+    fn constraints_are_respected() {
+        let points = fixture();
+        let dbscan = dbscan();
+        let cluster = |state: &ClusterState| {
+            FuzzyDBSCAN::cluster_levels(std::slice::from_ref(&dbscan), &points, state)
+                .pop()
+                .unwrap()
+        };
+        let shared = |clusters: &[Cluster], a: usize, b: usize| {
+            clusters.iter().any(|cluster| {
+                cluster.iter().any(|assignment| assignment.index == a)
+                    && cluster.iter().any(|assignment| assignment.index == b)
+            })
+        };
+
+        // linking the first two rings and an outlier joins them into one cluster
+        let mut state = ClusterState::new(points.len());
+        state.constrain(&[(0, 8), (8, 42)], &[]);
+        let clusters = cluster(&state);
+        assert!(shared(&clusters, 3, 42) && shared(&clusters, 5, 20));
+        assert_eq!(clusters.len(), dbscan.cluster(&points).len() - 1);
+
+        // opposite points of a ring end up in different clusters
+        let mut state = ClusterState::new(points.len());
+        state.constrain(&[], &[(22, 32)]);
+        let clusters = cluster(&state);
+        assert!(!shared(&clusters, 22, 32));
+        let noise = clusters
+            .last()
+            .filter(|cluster| crate::analysis::is_noise(cluster));
+        assert!(noise.is_none_or(|noise| noise.iter().all(|a| a.index >= 42)));
+        // the blocked point starts its own cluster instead of a copy of the ring
+        let mut cores: Vec<usize> = clusters
+            .iter()
+            .flatten()
+            .filter(|assignment| assignment.category == Category::Core)
+            .map(|assignment| assignment.index)
+            .collect();
+        let core_count = cores.len();
+        cores.sort_unstable();
+        cores.dedup();
+        assert_eq!(cores.len(), core_count);
+        assert_eq!(clusters.len(), dbscan.cluster(&points).len() + 1);
+
+        assert_eq!(ClusterState::from_bytes(&state.to_bytes()).unwrap(), state);
+    }
+
+    #[test]
+    fn eps_sweep_matches_independent_clusterings() {
+        let mut rng = crate::rng::Rng::new(3);
+        let mut points = fixture();
+        points.extend((0..60).map(|_| Point(rng.gaussian() * 4., rng.gaussian() * 4.)));
+        let levels: Vec<FuzzyDBSCAN> = [0.2, 1.5, 0.6, 0.9, 1.2, 3., 0.05]
+            .iter()
+            .map(|&eps| FuzzyDBSCAN {
+                eps_min: eps * 0.5,
+                eps_max: eps,
+                ..dbscan()
+            })
+            .collect();
+        let sweep = FuzzyDBSCAN::sweep_eps(&levels, &points);
+        let mut join_eps = vec![f64::INFINITY; points.len()];
+        for (level, result) in levels.iter().zip(&sweep.levels) {
+            let clusters = level.cluster(&points);
+            for assignment in clusters.iter().flatten() {
+                if assignment.category != Category::Noise {
+                    join_eps[assignment.index] = join_eps[assignment.index].min(level.eps_max);
+                }
+            }
+            let noise = clusters
+                .last()
+                .filter(|cluster| crate::analysis::is_noise(cluster))
+                .map_or(0, |noise| noise.len());
+            let expected = SweepLevel {
+                cluster_count: clusters.len() - usize::from(noise > 0),
+                noise_count: noise,
+            };
+            assert_eq!(*result, expected, "eps {}", level.eps_max);
+        }
+        let swept_join_eps: Vec<f64> = sweep
+            .join_levels
+            .iter()
+            .map(|level| level.map_or(f64::INFINITY, |level| levels[level].eps_max))
+            .collect();
+        assert_eq!(swept_join_eps, join_eps);
+        assert!(sweep.levels.iter().any(|result| result.cluster_count > 1));
+        assert!(sweep.levels.iter().any(|result| result.noise_count > 0));
+        assert!(join_eps.iter().any(|eps| eps.is_infinite()));
+    }
+
+    #[test]
+    fn updated_clustering_matches_full_clustering() {
+        let mut rng = crate::rng::Rng::new(7);
+        let mut points = fixture();
+        let mut clusters = dbscan().cluster(&points);
+        for _ in 0..30 {
+            let changed: Vec<usize> = (0..3)
+                .map(|_| (rng.uniform() * points.len() as f64) as usize)
+                .filter(|&index| index < points.len())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            let previous: Vec<Point> = changed
+                .iter()
+                .map(|&index| Point(points[index].0, points[index].1))
+                .collect();
+            for &index in &changed {
+                // mostly small moves within a ring, sometimes a jump that can bridge or split clusters
+                let scale = if rng.uniform() < 0.3 { 8. } else { 0.8 };
+                points[index].0 += scale * rng.gaussian();
+                points[index].1 += scale * rng.gaussian();
+            }
+            clusters = dbscan().update(&points, &changed, &previous, &clusters);
+            assert_eq!(clusters, dbscan().cluster(&points));
+        }
+    }
+
+    #[test]
+    fn moved_index_matches_scan() {
+        let mut rng = crate::rng::Rng::new(3);
+        let mut points: Vec<Planar> = fixture()
+            .into_iter()
+            .map(|point| Planar(point.0, point.1))
+            .collect();
+        let mut values: Vec<OnLine> = (0..100).map(|i| OnLine(i as f64 * 0.1)).collect();
+        let mut clusters = dbscan().cluster(&points);
+        let mut index = SpatialIndex::new(&points);
+        let mut line_index = SpatialIndex::new(&values);
+        // more moves than the square root of the point count rebuild the index on the way
+        for _ in 0..20 {
+            let changed = [(rng.uniform() * points.len() as f64) as usize % points.len()];
+            let previous: Vec<Planar> = changed
+                .iter()
+                .map(|&index| Planar(points[index].0, points[index].1))
+                .collect();
+            for &index in &changed {
+                points[index].0 += 3. * rng.gaussian();
+                points[index].1 += 3. * rng.gaussian();
+            }
+            index.move_points(&points, &changed);
+            assert!(index.is_accelerated());
+            clusters = dbscan().update_indexed(&points, &changed, &previous, &clusters, &index);
+            assert_eq!(clusters, dbscan().cluster(&points));
+
+            let moved = (rng.uniform() * values.len() as f64) as usize % values.len();
+            values[moved].0 += rng.gaussian();
+            line_index.move_points(&values, &[moved]);
+            for point_index in [0, moved, 50] {
+                let expected: Vec<usize> = (0..values.len())
+                    .filter(|&i| {
+                        i != point_index && values[i].distance(&values[point_index]) <= 0.25
+                    })
+                    .collect();
+                assert_eq!(line_index.neighbors(&values, point_index, 0.25), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn border_points_name_their_core() {
+        let mut points = fixture();
+        // a border point of the first ring, closest to its point at (1, 0)
+        points.push(Point(1.9, 0.));
+        let clusters = dbscan().cluster(&points);
+        for cluster in &clusters {
+            for assignment in cluster {
+                let Some(core) = assignment.core_index else {
+                    continue;
+                };
+                assert_eq!(assignment.category, Category::Border);
+                assert!(cluster
+                    .iter()
+                    .any(|a| a.index == core && a.category == Category::Core));
+                let distance = points[assignment.index].distance(&points[core]);
+                assert_eq!(assignment.core_distance, Some(distance));
+            }
+        }
+        let border = clusters[0]
+            .iter()
+            .find(|assignment| assignment.index == points.len() - 1)
+            .expect("border point in the first ring");
+        assert_eq!(border.core_index, Some(0));
+    }
+
+    #[test]
+    fn weights_count_towards_density() {
+        struct Heavy(f64, f64);
+
+        impl MetricSpace for Heavy {
+            fn distance(&self, other: &Self) -> f64 {
+                (other.0 - self.0).abs()
+            }
+
+            fn weight(&self) -> f64 {
+                self.1
+            }
+        }
+
+        let dbscan = dbscan();
+        // a lone point is noise unless it counts as many points
+        let light = dbscan.cluster(&[Heavy(0., 1.), Heavy(10., 1.)]);
+        assert_eq!(light.len(), 1);
+        let heavy = dbscan.cluster(&[Heavy(0., 4.), Heavy(0.9, 0.), Heavy(10., 1.)]);
+        assert_eq!(heavy.len(), 2);
+        assert_eq!(heavy[0][0].index, 0);
+        assert_eq!(heavy[0][0].category, Category::Core);
+        // a point without weight still joins as a border point, but doesn't add to densities
+        assert!(heavy[0]
+            .iter()
+            .any(|assignment| assignment.index == 1 && assignment.category == Category::Border));
+        assert_eq!(heavy[1].len(), 1);
+    }
+
+    #[test]
+    fn indexed_neighbors_match_scan() {
+        let mut rng = crate::rng::Rng::new(5);
+        let points: Vec<OnLine> = (0..200).map(|_| OnLine(rng.uniform() * 10.)).collect();
+        let index = SpatialIndex::new(&points);
+        assert!(index.is_accelerated());
+        for point_index in [0, 17, 199] {
+            let expected: Vec<usize> = (0..points.len())
+                .filter(|&i| i != point_index && points[i].distance(&points[point_index]) <= 0.3)
+                .collect();
+            assert_eq!(index.neighbors(&points, point_index, 0.3), expected);
+        }
+        assert!(!SpatialIndex::new(&fixture()).is_accelerated());
+    }
+
+    #[test]
+    fn truncated_state_is_rejected() {
+        let points = fixture();
+        let mut state = ClusterState::new(points.len());
+        dbscan().step(&points, &mut state, 10);
+        let bytes = state.to_bytes();
+        assert!(matches!(
+            ClusterState::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ClusterError::InvalidState)
+        ));
+    }
+}
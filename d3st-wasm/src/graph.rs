@@ -0,0 +1,56 @@
+//! Graphs over points.
+
+use crate::fuzzy_dbscan::MetricSpace;
+
+/// An edge between points `a` and `b` with the distance between them as weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub a: usize,
+    pub b: usize,
+    pub weight: f64,
+}
+
+/// Returns the edges of a minimum spanning tree of the complete graph over `points`, sorted by increasing
+/// weight (ties keep the order in which they were found).
+///
+/// Uses Prim's algorithm on the implicit complete graph, which measures each pair of points once: `O(n²)` distance
+/// computations, but only `O(n)` memory. Points at a non-finite distance from every other point (e.g. with NaN
+/// coordinates) are connected with an infinite weight so the result is always a tree of `n - 1` edges.
+pub fn minimum_spanning_tree<P: MetricSpace>(points: &[P]) -> Vec<Edge> {
+    let point_count = points.len();
+    let mut edges = Vec::with_capacity(point_count.saturating_sub(1));
+    if point_count < 2 {
+        return edges;
+    }
+    let mut in_tree = vec![false; point_count];
+    // cheapest known edge from each point outside the tree into the tree
+    let mut cheapest = vec![(f64::INFINITY, 0); point_count];
+    let mut newest = 0;
+    in_tree[newest] = true;
+    for _ in 1..point_count {
+        let mut next: Option<(usize, f64)> = None;
+        for (index, candidate) in cheapest.iter_mut().enumerate() {
+            if in_tree[index] {
+                continue;
+            }
+            let distance = points[newest].distance(&points[index]);
+            if distance < candidate.0 {
+                *candidate = (distance, newest);
+            }
+            if next.is_none_or(|(_, weight)| candidate.0 < weight) {
+                next = Some((index, candidate.0));
+            }
+        }
+        let (next, _) = next.expect("a point is outside the tree");
+        let (weight, parent) = cheapest[next];
+        edges.push(Edge {
+            a: parent,
+            b: next,
+            weight,
+        });
+        in_tree[next] = true;
+        newest = next;
+    }
+    edges.sort_by(|x, y| x.weight.total_cmp(&y.weight));
+    edges
+}
@@ -1,175 +1,113 @@
-use thiserror::Error;
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
-
-mod fuzzy_dbscan;
+//! FuzzyDBSCAN clustering for d3-scattertrans.
+//!
+//! With the default `wasm` feature, `FuzzyCluster` exposes the clustering to JavaScript with packed typed-array
+//! input and output. Rust consumers can use the algorithm directly through the [`fuzzy_dbscan`] module and read
+//! packed data with the [`packing`] helpers; disable default features to build without `wasm-bindgen`.
 
-#[wasm_bindgen]
-pub struct FuzzyCluster {
-    inner: fuzzy_dbscan::FuzzyDBSCAN,
-}
+use thiserror::Error;
 
+pub mod analysis;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod diagnostics;
+pub mod fuzzy_dbscan;
+pub mod graph;
+#[cfg(feature = "messagepack")]
+pub mod messagepack;
+pub mod metric;
+pub mod packing;
+mod rng;
+pub mod spatial;
+pub mod transform;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+
+/// Errors when reading input or clustering.
 #[derive(Debug, Error)]
 pub enum ClusterError {
     #[error("unexpected end of input")]
     UnexpectedEndOfInput,
-}
-
-impl FuzzyCluster {
-    /// Clusters data. Uses const implementations for up to 7 dimensions.
-    ///
-    /// # Parameters
-    /// - packed_data: Float32Array of data point values
-    /// - dimensions: number of dimensions per data point
-    ///
-    /// # Return Value
-    /// Returns packed clusters: u16 array of (point count, (index | (category << 14), soft label)+).
-    fn cluster(&self, packed_data: Vec<u8>, dimensions: usize) -> Result<Vec<u8>, ClusterError> {
-        macro_rules! const_dims {
-            ($($dim:tt),+) => {
-                match dimensions {
-                    $(
-                    $dim => {
-                        let data = read_packed_data::<$dim>(&packed_data)?;
-                        let clusters = self.inner.cluster(&data);
-                        pack_clusters(&clusters)
-                    }
-                    )+
-                    _ => {
-                        let data = read_packed_data_dyn(&packed_data, dimensions)?;
-                        let clusters = self.inner.cluster(&data);
-                        pack_clusters(&clusters)
-                    }
-                }
-            }
-        }
-
-        Ok(const_dims!(1, 2, 3, 4, 5, 6, 7))
-    }
-}
-
-#[wasm_bindgen]
-impl FuzzyCluster {
-    #[wasm_bindgen(constructor)]
-    pub fn new(eps_min: f64, eps_max: f64, pts_min: f64, pts_max: f64) -> Self {
-        FuzzyCluster {
-            inner: fuzzy_dbscan::FuzzyDBSCAN {
-                eps_min,
-                eps_max,
-                pts_min,
-                pts_max,
-            },
-        }
-    }
-
-    #[wasm_bindgen(js_name = "cluster")]
-    pub fn cluster_js(&self, packed_data: Vec<u8>, dimensions: usize) -> Result<Vec<u8>, JsValue> {
-        self.cluster(packed_data, dimensions)
-            .map_err(|err| JsValue::from(format!("{}", err)))
-    }
-}
-
-type PointScalar = f32;
-struct DataPoint<'a, const N: usize> {
-    data: &'a [PointScalar],
-}
-
-impl<'a, const N: usize> fuzzy_dbscan::MetricSpace for DataPoint<'a, N> {
-    fn distance(&self, other: &Self) -> f64 {
-        let mut sum = 0.;
-        for i in 0..N {
-            let diff = self.data[i] - other.data[i];
-            sum += (diff * diff) as f64;
-        }
-        sum.sqrt()
-    }
-}
-
-struct DataPointDyn<'a> {
-    data: &'a [PointScalar],
-    dimensions: usize,
-}
-
-impl<'a> fuzzy_dbscan::MetricSpace for DataPointDyn<'a> {
-    fn distance(&self, other: &Self) -> f64 {
-        let mut sum = 0.;
-        for i in 0..self.dimensions {
-            let diff = self.data[i] - other.data[i];
-            sum += (diff * diff) as f64;
-        }
-        sum.sqrt()
-    }
-}
-
-fn byte_array_as_scalar_type(arr: &[u8]) -> Result<&[PointScalar], ClusterError> {
-    if arr.len() % std::mem::size_of::<PointScalar>() != 0 {
-        return Err(ClusterError::UnexpectedEndOfInput);
-    }
-    let transmuted_len = arr.len() / std::mem::size_of::<PointScalar>();
-    // SAFETY: array length has been checked above, so this transmutation should be safe
-    Ok(unsafe { std::slice::from_raw_parts(arr.as_ptr() as *const PointScalar, transmuted_len) })
-}
-
-fn read_packed_data<const N: usize>(
-    packed_data: &[u8],
-) -> Result<Vec<DataPoint<N>>, ClusterError> {
-    let packed_data = byte_array_as_scalar_type(packed_data)?;
-    if packed_data.len() % N != 0 {
-        return Err(ClusterError::UnexpectedEndOfInput);
-    }
-    let point_count = packed_data.len() / N;
-    let mut points = Vec::with_capacity(point_count);
-    for i in 0..point_count {
-        points.push(DataPoint {
-            data: &packed_data[(i * N)..((i + 1) * N)],
-        });
-    }
-    Ok(points)
-}
-
-fn read_packed_data_dyn(
-    packed_data: &[u8],
-    dimensions: usize,
-) -> Result<Vec<DataPointDyn>, ClusterError> {
-    let packed_data = byte_array_as_scalar_type(packed_data)?;
-    if packed_data.len() % dimensions != 0 {
-        return Err(ClusterError::UnexpectedEndOfInput);
-    }
-    let point_count = packed_data.len() / dimensions;
-    let mut points = Vec::with_capacity(point_count);
-    for i in 0..point_count {
-        points.push(DataPointDyn {
-            data: &packed_data[(i * dimensions)..((i + 1) * dimensions)],
-            dimensions,
-        });
-    }
-    Ok(points)
-}
-
-fn pack_clusters(clusters: &[fuzzy_dbscan::Cluster]) -> Vec<u8> {
-    let mut out: Vec<u16> = Vec::with_capacity(clusters.iter().map(|c| c.len() * 2 + 1).sum());
-    for cluster in clusters {
-        out.push(cluster.len() as u16);
-        for assignment in cluster {
-            let category_index = match assignment.category {
-                fuzzy_dbscan::Category::Core => 0,
-                fuzzy_dbscan::Category::Border => 1,
-                fuzzy_dbscan::Category::Noise => 2,
-            };
-            out.push(assignment.index as u16 | (category_index << 14));
-            out.push((assignment.label * 65535.) as u16);
-        }
-    }
-
-    // Vec::into_raw_parts is unstable, so we'll just do this
-    let (ptr, len, cap) = (out.as_ptr(), out.len(), out.capacity());
-    std::mem::forget(out);
-
-    // SAFETY: u16 is double the size of u8, so this should be fine
-    unsafe { Vec::from_raw_parts(ptr as *mut u8, len * 2, cap * 2) }
+    #[error("buffer of {len} bytes is not a multiple of the {scalar_size}-byte scalar size ({} stray bytes)", len % scalar_size)]
+    MisalignedBuffer { len: usize, scalar_size: usize },
+    #[error("invalid or truncated clustering state")]
+    InvalidState,
+    #[error("no clustering has been run yet")]
+    NoClustering,
+    #[error("no clustering is in progress")]
+    NoSession,
+    #[error("no points have been set")]
+    NoPoints,
+    #[error("eps_min ({eps_min}) is greater than eps_max ({eps_max})")]
+    InvertedEpsRange { eps_min: f64, eps_max: f64 },
+    #[error("eps value {index} is NaN")]
+    NanEps { index: usize },
+    #[error("an eps sweep can't apply {option}, so its counts would differ from count_clusters")]
+    UnsupportedSweepOption { option: &'static str },
+    #[error("{name} ({value}) is below {min_density}, the smallest density of a point without neighbors")]
+    PtsBelowMinDensity {
+        name: &'static str,
+        value: f64,
+        min_density: f64,
+    },
+    #[error("pts_min ({value}) is not below {total_weight}, the largest possible density of the clustered points, so every point would be noise")]
+    ParameterExceedsData { value: f64, total_weight: f64 },
+    #[error("the {metric} metric can't measure points with {dimensions} dimensions")]
+    MetricDimensionMismatch {
+        metric: &'static str,
+        dimensions: usize,
+    },
+    #[error("{dimensions} dimensions are not supported, at most {max} are")]
+    UnsupportedDimensions { dimensions: usize, max: usize },
+    #[error("clustering needs about {required} bytes, more than the budget of {budget} bytes")]
+    InsufficientMemory { required: usize, budget: usize },
+    #[error("points have {got} dimensions, but {expected} were clustered")]
+    DimensionMismatch { expected: usize, got: usize },
+    #[error("{param_name} has {got} values, but points have {expected} dimensions")]
+    DimensionVectorLengthMismatch {
+        param_name: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("{param_name} has {got} values, but there are {expected} points")]
+    PointVectorLengthMismatch {
+        param_name: &'static str,
+        expected: usize,
+        got: usize,
+    },
+    #[error("{dimensions} dimensions starting at offset {offset} don't fit in a stride of {stride} scalars")]
+    InvalidStride {
+        dimensions: usize,
+        stride: usize,
+        offset: usize,
+    },
+    #[error("point index {index} is out of range for {point_count} points")]
+    IndexOutOfRange { index: usize, point_count: usize },
+    #[error("index arrays have different lengths ({a} and {b})")]
+    LengthMismatch { a: usize, b: usize },
+    #[error("categorical dimension has the value {value}, but categories must be whole numbers from 0 to 65535")]
+    InvalidCategory { value: f32 },
+    #[error("a random projection would mix the categorical last dimension with the others")]
+    ProjectedCategories,
+    #[error("point {index} has the coordinate {value}, but the {metric} metric needs non-negative coordinates")]
+    NegativeCoordinate {
+        metric: &'static str,
+        index: usize,
+        value: f32,
+    },
+    #[error("point {index} has the weight {value}, but weights must be finite and non-negative")]
+    InvalidWeight { index: usize, value: f32 },
+    #[error(
+        "point index {index} doesn't fit in the packed format, which indexes at most {max} points"
+    )]
+    IndexExceedsFormat { index: usize, max: usize },
+    #[error("labels can have 8 or 16 bits, not {bits}")]
+    UnsupportedLabelBits { bits: u32 },
+    #[error("the result needs {required} bytes, but the buffer only has {len}")]
+    BufferTooSmall { required: usize, len: usize },
+    #[cfg(feature = "messagepack")]
+    #[error("invalid MessagePack cluster records: {0}")]
+    InvalidMessagePack(String),
 }
@@ -0,0 +1,146 @@
+//! Self-describing MessagePack encoding of clustering results.
+//!
+//! Unlike the packed u16 format, the encoding carries its field names, so it can be decoded with any
+//! MessagePack library, has no limit on point indices and keeps labels at full precision.
+
+use serde::{Deserialize, Serialize};
+
+use crate::fuzzy_dbscan::{Assignment, Category, Cluster};
+use crate::ClusterError;
+
+/// Clusters of a run, as stored in the encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterRecords {
+    /// Dimensions per input point.
+    pub dimensions: usize,
+    /// Number of input points.
+    pub point_count: usize,
+    /// The clusters, including the trailing noise group.
+    pub clusters: Vec<Vec<AssignmentRecord>>,
+}
+
+/// A point in a [cluster record](ClusterRecords).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignmentRecord {
+    pub index: usize,
+    /// `"core"`, `"border"`, `"noise"` or `"reassigned"`.
+    pub category: CategoryRecord,
+    pub label: f64,
+}
+
+/// [`Category`] as a lowercase string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategoryRecord {
+    Core,
+    Border,
+    Noise,
+    Reassigned,
+}
+
+impl From<Category> for CategoryRecord {
+    fn from(category: Category) -> Self {
+        match category {
+            Category::Core => CategoryRecord::Core,
+            Category::Border => CategoryRecord::Border,
+            Category::Noise => CategoryRecord::Noise,
+            Category::Reassigned => CategoryRecord::Reassigned,
+        }
+    }
+}
+
+impl From<CategoryRecord> for Category {
+    fn from(category: CategoryRecord) -> Self {
+        match category {
+            CategoryRecord::Core => Category::Core,
+            CategoryRecord::Border => Category::Border,
+            CategoryRecord::Noise => Category::Noise,
+            CategoryRecord::Reassigned => Category::Reassigned,
+        }
+    }
+}
+
+impl ClusterRecords {
+    pub fn new(clusters: &[Cluster], dimensions: usize, point_count: usize) -> Self {
+        let clusters = clusters
+            .iter()
+            .map(|cluster| {
+                cluster
+                    .iter()
+                    .map(|assignment| AssignmentRecord {
+                        index: assignment.index,
+                        category: assignment.category.into(),
+                        label: assignment.label,
+                    })
+                    .collect()
+            })
+            .collect();
+        ClusterRecords {
+            dimensions,
+            point_count,
+            clusters,
+        }
+    }
+
+    /// Converts the records back into clusters. The encoding doesn't store core distances or core indices, so they
+    /// are `None`.
+    pub fn into_clusters(self) -> Vec<Cluster> {
+        self.clusters
+            .into_iter()
+            .map(|cluster| {
+                cluster
+                    .into_iter()
+                    .map(|record| Assignment {
+                        index: record.index,
+                        label: record.label,
+                        category: record.category.into(),
+                        core_distance: None,
+                        core_index: None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Encodes the records as MessagePack maps with named fields.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        rmp_serde::to_vec_named(self).expect("cluster records are always serializable")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClusterError> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|err| ClusterError::InvalidMessagePack(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_keeps_assignments() {
+        let assignment = |index, label, category| Assignment {
+            index,
+            label,
+            category,
+            core_distance: None,
+            core_index: None,
+        };
+        let clusters = vec![
+            vec![
+                assignment(0, 1., Category::Core),
+                assignment(20000, 0.123456789, Category::Border),
+            ],
+            vec![assignment(3, 0., Category::Noise)],
+        ];
+        let records = ClusterRecords::new(&clusters, 2, 20001);
+        let decoded = ClusterRecords::from_bytes(&records.to_bytes()).unwrap();
+        assert_eq!(decoded, records);
+        assert_eq!(decoded.into_clusters(), clusters);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(ClusterRecords::from_bytes(&[0xc1, 0x00]).is_err());
+    }
+}
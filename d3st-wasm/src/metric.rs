@@ -0,0 +1,311 @@
+//! Distance functions for points with a runtime number of dimensions.
+
+use crate::fuzzy_dbscan::MetricSpace;
+use crate::packing::{euclidean_within, PointScalar};
+use crate::ClusterError;
+
+/// How the distance between two points is measured.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Metric {
+    /// Euclidean distance over all dimensions.
+    #[default]
+    Euclidean,
+    /// Blends the Euclidean distance of the first `split` dimensions (e.g. screen position) with the Euclidean
+    /// distance of the remaining ones (e.g. feature attributes):
+    /// `alpha * spatial + (1 - alpha) * feature`.
+    Blended { split: usize, alpha: f64 },
+    /// Euclidean distance where dimension `i` wraps around after `periods[i]` (e.g. 360 for angles), so the
+    /// difference along it is the shorter way around the circle. A period of 0 or NaN marks a regular dimension.
+    Periodic { periods: Vec<PointScalar> },
+    /// Planar distance between 2D points given as (radius, angle in radians), e.g. in a radial layout.
+    Polar,
+    /// `1 - r`, where `r` is the Pearson correlation of the coordinates of two points, for feature vectors whose
+    /// shape matters more than their magnitude (e.g. time series). Ranges from 0 for perfectly correlated to 2 for
+    /// anti-correlated points; a point whose coordinates are all equal has distance 1 to every point.
+    Correlation,
+    /// Jaccard distance `1 - |A ∩ B| / |A ∪ B|` between the sets of dimensions in which two points have a
+    /// coordinate of at least 0.5, for binary features such as tags. Two points without any such dimension have
+    /// distance 0.
+    Jaccard,
+    /// Jensen-Shannon divergence (in bits, so from 0 to 1) between points whose coordinates are histograms or
+    /// probability distributions over the dimensions. Coordinates must be non-negative, which
+    /// [`validate_data`](Metric::validate_data) checks; they are smoothed by [`JS_SMOOTHING`] and normalized to sum
+    /// to 1, so unnormalized counts and empty bins are fine.
+    JensenShannon,
+    /// Distance induced by a Gaussian kernel, `sqrt(2 - 2 * exp(-gamma * e^2))` for the Euclidean distance `e`.
+    /// It grows like `sqrt(2 * gamma) * e` for close points but levels off at `sqrt(2)` for distant ones, so only
+    /// local structure matters. An eps for Euclidean distances corresponds to `sqrt(2 - 2 * exp(-gamma * eps^2))`,
+    /// and an eps of `sqrt(2)` or more includes every point.
+    Kernel { gamma: f64 },
+    /// Treats the last dimension as a category, e.g. a class: points in different categories are infinitely far
+    /// apart, so they never share a cluster, and points in the same category are measured with `inner` over the
+    /// other dimensions. Categories are compared for equality only.
+    Categorical { inner: Box<Metric> },
+}
+
+/// Largest value the categorical dimension of [`Metric::Categorical`] may have.
+pub const MAX_CATEGORY: PointScalar = 65535.;
+
+/// Added to every coordinate by [`Metric::JensenShannon`] before normalizing, which keeps empty bins from
+/// producing `log(0)`.
+pub const JS_SMOOTHING: f64 = 1e-9;
+
+impl Metric {
+    /// Returns a short name of the metric for messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Euclidean => "euclidean",
+            Metric::Blended { .. } => "blended",
+            Metric::Periodic { .. } => "periodic",
+            Metric::Polar => "polar",
+            Metric::Correlation => "correlation",
+            Metric::Jaccard => "jaccard",
+            Metric::JensenShannon => "jensen-shannon",
+            Metric::Kernel { .. } => "kernel",
+            Metric::Categorical { .. } => "categorical",
+        }
+    }
+
+    /// Checks that the metric can measure points with `dimensions` dimensions.
+    pub fn validate(&self, dimensions: usize) -> Result<(), ClusterError> {
+        let valid = match self {
+            Metric::Euclidean => true,
+            Metric::Blended { split, .. } => *split <= dimensions,
+            Metric::Periodic { periods } => {
+                check_dimension_vector("periods", periods, dimensions)?;
+                true
+            }
+            Metric::Polar => dimensions == 2,
+            Metric::Categorical { inner } => {
+                return match dimensions.checked_sub(1) {
+                    Some(dimensions) => inner.validate(dimensions),
+                    None => Err(ClusterError::MetricDimensionMismatch {
+                        metric: self.name(),
+                        dimensions,
+                    }),
+                };
+            }
+            Metric::Correlation
+            | Metric::Jaccard
+            | Metric::JensenShannon
+            | Metric::Kernel { .. } => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(ClusterError::MetricDimensionMismatch {
+                metric: self.name(),
+                dimensions,
+            })
+        }
+    }
+
+    /// Checks that packed points with `dimensions` scalars each fit the metric beyond their dimensions: the
+    /// categories of [`Metric::Categorical`] must be whole numbers from 0 to [`MAX_CATEGORY`], and the coordinates
+    /// of [`Metric::JensenShannon`] must not be negative.
+    pub fn validate_data(
+        &self,
+        data: &[PointScalar],
+        dimensions: usize,
+    ) -> Result<(), ClusterError> {
+        match self {
+            Metric::JensenShannon => check_non_negative(self.name(), data, dimensions, dimensions)?,
+            Metric::Categorical { inner } => {
+                let invalid = data
+                    .chunks_exact(dimensions)
+                    .map(|point| point[dimensions - 1])
+                    .find(|&category| {
+                        !(0. ..=MAX_CATEGORY).contains(&category) || category.fract() != 0.
+                    });
+                if let Some(value) = invalid {
+                    return Err(ClusterError::InvalidCategory { value });
+                }
+                if let Metric::JensenShannon = **inner {
+                    check_non_negative(inner.name(), data, dimensions, dimensions - 1)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the distance between `a` and `b`, which must have the same length.
+    pub fn distance(&self, a: &[PointScalar], b: &[PointScalar]) -> f64 {
+        match self {
+            Metric::Euclidean => euclidean(a, b),
+            Metric::Blended { split, alpha } => {
+                let spatial = euclidean(&a[..*split], &b[..*split]);
+                let feature = euclidean(&a[*split..], &b[*split..]);
+                alpha * spatial + (1. - alpha) * feature
+            }
+            Metric::Periodic { periods } => {
+                let mut sum = 0.;
+                for ((a, b), &period) in a.iter().zip(b).zip(periods) {
+                    let mut diff = (a - b).abs();
+                    if period > 0. {
+                        diff %= period;
+                        diff = diff.min(period - diff);
+                    }
+                    sum += (diff * diff) as f64;
+                }
+                sum.sqrt()
+            }
+            Metric::Polar => {
+                let (r1, r2) = (a[0] as f64, b[0] as f64);
+                let angle = (a[1] - b[1]) as f64;
+                // law of cosines; rounding can make the square slightly negative for identical points
+                (r1 * r1 + r2 * r2 - 2. * r1 * r2 * angle.cos())
+                    .max(0.)
+                    .sqrt()
+            }
+            Metric::Correlation => 1. - correlation(a, b),
+            Metric::Jaccard => {
+                let (mut intersection, mut union) = (0usize, 0usize);
+                for (&a, &b) in a.iter().zip(b) {
+                    let (a, b) = (a >= 0.5, b >= 0.5);
+                    intersection += (a && b) as usize;
+                    union += (a || b) as usize;
+                }
+                if union == 0 {
+                    0.
+                } else {
+                    1. - intersection as f64 / union as f64
+                }
+            }
+            Metric::JensenShannon => jensen_shannon(a, b),
+            Metric::Kernel { gamma } => {
+                let squared = euclidean(a, b).powi(2);
+                // rounding can make the difference slightly negative for identical points
+                (2. - 2. * (-gamma * squared).exp()).max(0.).sqrt()
+            }
+            Metric::Categorical { inner } => {
+                let last = a.len() - 1;
+                if a[last] == b[last] {
+                    inner.distance(&a[..last], &b[..last])
+                } else {
+                    f64::INFINITY
+                }
+            }
+        }
+    }
+}
+
+/// Checks that a parameter with one value per dimension, such as the periods of [`Metric::Periodic`], has
+/// exactly `dimensions` values.
+pub fn check_dimension_vector<T>(
+    param_name: &'static str,
+    values: &[T],
+    dimensions: usize,
+) -> Result<(), ClusterError> {
+    if values.len() == dimensions {
+        Ok(())
+    } else {
+        Err(ClusterError::DimensionVectorLengthMismatch {
+            param_name,
+            expected: dimensions,
+            got: values.len(),
+        })
+    }
+}
+
+/// Checks that the first `measured` of the `dimensions` coordinates of each packed point are not negative, as
+/// `metric` needs. NaN coordinates pass, since they are reported as warnings for every metric.
+fn check_non_negative(
+    metric: &'static str,
+    data: &[PointScalar],
+    dimensions: usize,
+    measured: usize,
+) -> Result<(), ClusterError> {
+    for (index, point) in data.chunks_exact(dimensions).enumerate() {
+        if let Some(&value) = point[..measured].iter().find(|&&value| value < 0.) {
+            return Err(ClusterError::NegativeCoordinate {
+                metric,
+                index,
+                value,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Pearson correlation of `a` and `b`, or 0 if either has no variance.
+fn correlation(a: &[PointScalar], b: &[PointScalar]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let mean_b = b.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let (mut covariance, mut variance_a, mut variance_b) = (0., 0., 0.);
+    for (&a, &b) in a.iter().zip(b) {
+        let (da, db) = (a as f64 - mean_a, b as f64 - mean_b);
+        covariance += da * db;
+        variance_a += da * da;
+        variance_b += db * db;
+    }
+    if variance_a == 0. || variance_b == 0. {
+        return 0.;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+/// Jensen-Shannon divergence in bits of the smoothed and normalized distributions `a` and `b`.
+fn jensen_shannon(a: &[PointScalar], b: &[PointScalar]) -> f64 {
+    let smoothed_sum =
+        |values: &[PointScalar]| values.iter().map(|&v| v as f64 + JS_SMOOTHING).sum::<f64>();
+    let (sum_a, sum_b) = (smoothed_sum(a), smoothed_sum(b));
+    let mut divergence = 0.;
+    for (&a, &b) in a.iter().zip(b) {
+        let p = (a as f64 + JS_SMOOTHING) / sum_a;
+        let q = (b as f64 + JS_SMOOTHING) / sum_b;
+        let m = (p + q) / 2.;
+        divergence += p * (p / m).log2() + q * (q / m).log2();
+    }
+    // rounding can make the divergence of identical distributions slightly negative
+    (divergence / 2.).max(0.)
+}
+
+fn euclidean(a: &[PointScalar], b: &[PointScalar]) -> f64 {
+    let mut sum = 0.;
+    for (a, b) in a.iter().zip(b) {
+        let diff = a - b;
+        sum += (diff * diff) as f64;
+    }
+    sum.sqrt()
+}
+
+/// A point measured with a [`Metric`].
+#[derive(Clone, Copy)]
+pub struct MetricPoint<'a> {
+    pub data: &'a [PointScalar],
+    pub metric: &'a Metric,
+}
+
+impl<'a> MetricSpace for MetricPoint<'a> {
+    fn distance(&self, other: &Self) -> f64 {
+        self.metric.distance(self.data, other.data)
+    }
+
+    fn distance_within(&self, other: &Self, threshold: f64) -> Option<f64> {
+        match self.metric {
+            Metric::Euclidean => euclidean_within(self.data, other.data, threshold),
+            _ => {
+                let distance = self.distance(other);
+                (distance <= threshold).then_some(distance)
+            }
+        }
+    }
+
+    fn coordinates(&self) -> Option<Vec<f64>> {
+        matches!(self.metric, Metric::Euclidean)
+            .then(|| self.data.iter().map(|&value| value as f64).collect())
+    }
+}
+
+/// Splits packed scalars into points measured with `metric`.
+pub fn metric_points<'a>(
+    data: &'a [PointScalar],
+    dimensions: usize,
+    metric: &'a Metric,
+) -> Vec<MetricPoint<'a>> {
+    data.chunks_exact(dimensions)
+        .map(|data| MetricPoint { data, metric })
+        .collect()
+}
@@ -0,0 +1,560 @@
+//! Reading packed point data and packing cluster results.
+//!
+//! These helpers don't depend on JavaScript and are available without the `wasm` feature.
+
+use crate::{analysis, fuzzy_dbscan, ClusterError};
+
+/// Scalar type of packed input data.
+pub type PointScalar = f32;
+
+/// A point with `N` dimensions, measured with the Euclidean distance.
+#[derive(Clone, Copy)]
+pub struct DataPoint<'a, const N: usize> {
+    pub data: &'a [PointScalar],
+}
+
+impl<'a, const N: usize> fuzzy_dbscan::MetricSpace for DataPoint<'a, N> {
+    fn distance(&self, other: &Self) -> f64 {
+        let mut sum = 0.;
+        for i in 0..N {
+            let diff = self.data[i] - other.data[i];
+            sum += (diff * diff) as f64;
+        }
+        sum.sqrt()
+    }
+
+    fn distance_within(&self, other: &Self, threshold: f64) -> Option<f64> {
+        euclidean_within(&self.data[..N], &other.data[..N], threshold)
+    }
+
+    fn line_position(&self) -> Option<f64> {
+        (N == 1).then(|| self.data[0] as f64)
+    }
+
+    fn coordinates(&self) -> Option<Vec<f64>> {
+        Some(self.data[..N].iter().map(|&value| value as f64).collect())
+    }
+}
+
+/// A point with a runtime number of dimensions, measured with the Euclidean distance.
+#[derive(Clone, Copy)]
+pub struct DataPointDyn<'a> {
+    pub data: &'a [PointScalar],
+    pub dimensions: usize,
+}
+
+impl<'a> fuzzy_dbscan::MetricSpace for DataPointDyn<'a> {
+    fn distance(&self, other: &Self) -> f64 {
+        let mut sum = 0.;
+        for i in 0..self.dimensions {
+            let diff = self.data[i] - other.data[i];
+            sum += (diff * diff) as f64;
+        }
+        sum.sqrt()
+    }
+
+    fn distance_within(&self, other: &Self, threshold: f64) -> Option<f64> {
+        euclidean_within(&self.data[..self.dimensions], other.data, threshold)
+    }
+
+    fn coordinates(&self) -> Option<Vec<f64>> {
+        Some(
+            self.data[..self.dimensions]
+                .iter()
+                .map(|&value| value as f64)
+                .collect(),
+        )
+    }
+}
+
+/// Returns the Euclidean distance between `a` and `b` if it is at most `threshold`, stopping as soon as the sum
+/// of squares exceeds `threshold²`. The sum is checked after every [`EARLY_OUT_STRIDE`] dimensions.
+pub(crate) fn euclidean_within(
+    a: &[PointScalar],
+    b: &[PointScalar],
+    threshold: f64,
+) -> Option<f64> {
+    let limit = threshold * threshold;
+    let mut sum = 0.;
+    for (a, b) in a.chunks(EARLY_OUT_STRIDE).zip(b.chunks(EARLY_OUT_STRIDE)) {
+        for (a, b) in a.iter().zip(b) {
+            let diff = a - b;
+            sum += (diff * diff) as f64;
+        }
+        if sum > limit {
+            return None;
+        }
+    }
+    (sum <= limit).then(|| sum.sqrt())
+}
+
+/// Number of dimensions summed between checks in [`euclidean_within`]; checking after every dimension costs more
+/// than it saves.
+const EARLY_OUT_STRIDE: usize = 4;
+
+/// Reinterprets bytes as packed scalars, failing with [`ClusterError::MisalignedBuffer`] if the length is not a
+/// multiple of the scalar size.
+pub fn byte_array_as_scalar_type(arr: &[u8]) -> Result<&[PointScalar], ClusterError> {
+    let scalar_size = std::mem::size_of::<PointScalar>();
+    if !arr.len().is_multiple_of(scalar_size) {
+        return Err(ClusterError::MisalignedBuffer {
+            len: arr.len(),
+            scalar_size,
+        });
+    }
+    let transmuted_len = arr.len() / scalar_size;
+    // SAFETY: array length has been checked above, so this transmutation should be safe
+    Ok(unsafe { std::slice::from_raw_parts(arr.as_ptr() as *const PointScalar, transmuted_len) })
+}
+
+/// Reads little-endian IEEE half-precision floats and widens them to scalars, failing with
+/// [`ClusterError::MisalignedBuffer`] if the length is odd.
+pub fn half_byte_array_as_scalars(arr: &[u8]) -> Result<Vec<PointScalar>, ClusterError> {
+    let scalar_size = std::mem::size_of::<half::f16>();
+    if !arr.len().is_multiple_of(scalar_size) {
+        return Err(ClusterError::MisalignedBuffer {
+            len: arr.len(),
+            scalar_size,
+        });
+    }
+    Ok(arr
+        .chunks_exact(scalar_size)
+        .map(|bytes| half::f16::from_le_bytes([bytes[0], bytes[1]]).to_f32())
+        .collect())
+}
+
+/// Reads packed data with `N` scalars per point.
+pub fn read_packed_data<const N: usize>(
+    packed_data: &[u8],
+) -> Result<Vec<DataPoint<'_, N>>, ClusterError> {
+    scalars_as_points(byte_array_as_scalar_type(packed_data)?)
+}
+
+/// Reads packed data with `dimensions` scalars per point.
+pub fn read_packed_data_dyn(
+    packed_data: &[u8],
+    dimensions: usize,
+) -> Result<Vec<DataPointDyn<'_>>, ClusterError> {
+    scalars_as_points_dyn(byte_array_as_scalar_type(packed_data)?, dimensions)
+}
+
+/// Splits scalars into points with `N` scalars each.
+pub fn scalars_as_points<const N: usize>(
+    data: &[PointScalar],
+) -> Result<Vec<DataPoint<'_, N>>, ClusterError> {
+    if !data.len().is_multiple_of(N) {
+        return Err(ClusterError::UnexpectedEndOfInput);
+    }
+    Ok(data
+        .chunks_exact(N)
+        .map(|data| DataPoint { data })
+        .collect())
+}
+
+/// Splits scalars into points with `dimensions` scalars each.
+pub fn scalars_as_points_dyn(
+    data: &[PointScalar],
+    dimensions: usize,
+) -> Result<Vec<DataPointDyn<'_>>, ClusterError> {
+    if !data.len().is_multiple_of(dimensions) {
+        return Err(ClusterError::UnexpectedEndOfInput);
+    }
+    Ok(data
+        .chunks_exact(dimensions)
+        .map(|data| DataPointDyn { data, dimensions })
+        .collect())
+}
+
+/// Reads interleaved points, where each point occupies `stride` scalars and its `dimensions` coordinates start at
+/// `offset` within them, e.g. from a vertex buffer that also holds attributes which should not be clustered.
+///
+/// Returns the coordinates packed with `dimensions` scalars per point. The data must consist of whole strides.
+pub fn read_interleaved(
+    data: &[PointScalar],
+    dimensions: usize,
+    stride: usize,
+    offset: usize,
+) -> Result<Vec<PointScalar>, ClusterError> {
+    if dimensions == 0 || offset + dimensions > stride {
+        return Err(ClusterError::InvalidStride {
+            dimensions,
+            stride,
+            offset,
+        });
+    }
+    if !data.len().is_multiple_of(stride) {
+        return Err(ClusterError::UnexpectedEndOfInput);
+    }
+    Ok(data
+        .chunks_exact(stride)
+        .flat_map(|point| &point[offset..offset + dimensions])
+        .copied()
+        .collect())
+}
+
+/// Version of the header written by [`pack_clusters_with_header`].
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Number of u16 words in the header written by [`pack_clusters_with_header`].
+pub const HEADER_WORDS: usize = 7;
+
+/// Header flag set by [`pack_clusters_without_noise`]: the noise group is left out, and every input point that
+/// appears in no cluster is noise.
+pub const FLAG_NOISE_OMITTED: u16 = 1;
+
+/// Header flag set for [`LabelBits::Eight`]: labels are bytes stored after the index words of each cluster.
+pub const FLAG_8_BIT_LABELS: u16 = 2;
+
+/// Number of points whose indices fit in the 14 index bits of an index word, see [`pack_clusters_with_header`].
+pub const MAX_INDEXED_POINTS: usize = 1 << 14;
+
+/// Factor that maps a soft label from 0 to 1 to a 16-bit label.
+pub const LABEL_SCALE: f64 = u16::MAX as f64;
+
+/// Factor that maps a soft label from 0 to 1 to an 8-bit label, see [`LabelBits::Eight`].
+pub const LABEL_SCALE_8: f64 = u8::MAX as f64;
+
+/// Width of the quantized soft labels in results with a header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabelBits {
+    /// Each cluster is (point count, (index | (category << 14), label)+), with labels scaled by [`LABEL_SCALE`].
+    #[default]
+    Sixteen,
+    /// Each cluster is (point count, (index | (category << 14))+, label bytes), with labels scaled by
+    /// [`LABEL_SCALE_8`] and stored in the order of the indices, two per word (first in the low byte), and padded
+    /// with a zero byte to a whole word. This saves a quarter of the size at 1/255 precision.
+    Eight,
+}
+
+impl LabelBits {
+    /// Returns the width for `bits`, which must be 8 or 16.
+    pub fn new(bits: u32) -> Result<Self, ClusterError> {
+        match bits {
+            8 => Ok(LabelBits::Eight),
+            16 => Ok(LabelBits::Sixteen),
+            _ => Err(ClusterError::UnsupportedLabelBits { bits }),
+        }
+    }
+}
+
+/// Packs clusters as a u16 array of (point count, (index | (category << 14), soft label)+), returned as bytes.
+pub fn pack_clusters(clusters: &[fuzzy_dbscan::Cluster]) -> Vec<u8> {
+    let mut out: Vec<u16> = Vec::with_capacity(clusters.iter().map(|c| c.len() * 2 + 1).sum());
+    pack_clusters_into(clusters, |word| out.push(word));
+    u16_vec_into_bytes(out)
+}
+
+/// Returns the number of bytes [`pack_clusters`] produces: 2 per cluster plus 4 per assignment.
+pub fn packed_size(clusters: &[fuzzy_dbscan::Cluster]) -> usize {
+    clusters.iter().map(|c| c.len() * 4 + 2).sum()
+}
+
+/// Packs clusters like [`pack_clusters`] into the start of `out` and returns the number of bytes written.
+///
+/// Fails with [`ClusterError::BufferTooSmall`] without writing anything if `out` is shorter than
+/// [`packed_size`].
+pub fn pack_clusters_into_slice(
+    clusters: &[fuzzy_dbscan::Cluster],
+    out: &mut [u8],
+) -> Result<usize, ClusterError> {
+    let required = packed_size(clusters);
+    if out.len() < required {
+        return Err(ClusterError::BufferTooSmall {
+            required,
+            len: out.len(),
+        });
+    }
+    let mut words = out.chunks_exact_mut(2);
+    pack_clusters_into(clusters, |word| {
+        let bytes = words.next().expect("checked the size above");
+        bytes.copy_from_slice(&word.to_ne_bytes());
+    });
+    Ok(required)
+}
+
+/// Packs clusters like [`pack_clusters`], preceded by a header so decoders can allocate up front.
+///
+/// The header consists of these u16 words:
+/// - format version ([`FORMAT_VERSION`])
+/// - flags: [`FLAG_8_BIT_LABELS`] for 8-bit `label_bits` (see also [`FLAG_NOISE_OMITTED`])
+/// - dimensions per point
+/// - number of input points as u32 (low word first)
+/// - number of clusters as u32 (low word first), including the noise group
+///
+/// The counts are u32, but indices still have 14 bits, so this fails with [`ClusterError::IndexExceedsFormat`]
+/// if a cluster lists a point at index [`MAX_INDEXED_POINTS`] or above.
+pub fn pack_clusters_with_header(
+    clusters: &[fuzzy_dbscan::Cluster],
+    dimensions: usize,
+    point_count: usize,
+    label_bits: LabelBits,
+) -> Result<Vec<u8>, ClusterError> {
+    pack_with_header(clusters, 0, dimensions, point_count, label_bits)
+}
+
+/// Packs clusters like [`pack_clusters_with_header`], but leaves out the noise group and sets
+/// [`FLAG_NOISE_OMITTED`], which is much smaller when most points are noise.
+///
+/// Decoders treat every input point (`0..point_count` from the header) that no cluster lists as noise. The
+/// cluster count in the header only counts the listed clusters. Only the listed points need indices below
+/// [`MAX_INDEXED_POINTS`], so noise beyond them doesn't fail with [`ClusterError::IndexExceedsFormat`].
+pub fn pack_clusters_without_noise(
+    clusters: &[fuzzy_dbscan::Cluster],
+    dimensions: usize,
+    point_count: usize,
+    label_bits: LabelBits,
+) -> Result<Vec<u8>, ClusterError> {
+    let clusters = match clusters.last() {
+        Some(cluster) if analysis::is_noise(cluster) => &clusters[..clusters.len() - 1],
+        _ => clusters,
+    };
+    pack_with_header(
+        clusters,
+        FLAG_NOISE_OMITTED,
+        dimensions,
+        point_count,
+        label_bits,
+    )
+}
+
+fn pack_with_header(
+    clusters: &[fuzzy_dbscan::Cluster],
+    mut flags: u16,
+    dimensions: usize,
+    point_count: usize,
+    label_bits: LabelBits,
+) -> Result<Vec<u8>, ClusterError> {
+    // a cluster lists each point once, so its length fits in the count word as well
+    if let Some(assignment) = clusters
+        .iter()
+        .flatten()
+        .find(|assignment| assignment.index >= MAX_INDEXED_POINTS)
+    {
+        return Err(ClusterError::IndexExceedsFormat {
+            index: assignment.index,
+            max: MAX_INDEXED_POINTS,
+        });
+    }
+    let mut out: Vec<u16> =
+        Vec::with_capacity(HEADER_WORDS + clusters.iter().map(|c| c.len() * 2 + 1).sum::<usize>());
+    if label_bits == LabelBits::Eight {
+        flags |= FLAG_8_BIT_LABELS;
+    }
+    out.push(FORMAT_VERSION);
+    out.push(flags);
+    out.push(dimensions as u16);
+    push_u32(&mut out, point_count as u32);
+    push_u32(&mut out, clusters.len() as u32);
+    match label_bits {
+        LabelBits::Sixteen => pack_clusters_into(clusters, |word| out.push(word)),
+        LabelBits::Eight => pack_clusters_8_bit_into(clusters, &mut out),
+    }
+    Ok(u16_vec_into_bytes(out))
+}
+
+/// Appends the words of the [8-bit label](LabelBits::Eight) format of `clusters` to `out`.
+fn pack_clusters_8_bit_into(clusters: &[fuzzy_dbscan::Cluster], out: &mut Vec<u16>) {
+    for cluster in clusters {
+        out.push(cluster.len() as u16);
+        out.extend(cluster.iter().map(index_word));
+        let labels: Vec<u8> = cluster
+            .iter()
+            .map(|assignment| (assignment.label * LABEL_SCALE_8) as u8)
+            .collect();
+        out.extend(
+            labels
+                .chunks(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)])),
+        );
+    }
+}
+
+fn push_u32(out: &mut Vec<u16>, value: u32) {
+    out.push(value as u16);
+    out.push((value >> 16) as u16);
+}
+
+/// Passes the words of the packed format of `clusters` to `push` in order.
+fn pack_clusters_into(clusters: &[fuzzy_dbscan::Cluster], mut push: impl FnMut(u16)) {
+    for cluster in clusters {
+        push(cluster.len() as u16);
+        for assignment in cluster {
+            push(index_word(assignment));
+            push((assignment.label * LABEL_SCALE) as u16);
+        }
+    }
+}
+
+/// Returns the word holding the index and category of `assignment`.
+fn index_word(assignment: &fuzzy_dbscan::Assignment) -> u16 {
+    assignment.index as u16 | ((category_code(assignment.category) as u16) << 14)
+}
+
+/// Returns the number that encodes `category` in the packed format: 0 for core, 1 for border, 2 for noise and 3
+/// for reassigned points.
+pub fn category_code(category: fuzzy_dbscan::Category) -> u8 {
+    match category {
+        fuzzy_dbscan::Category::Core => 0,
+        fuzzy_dbscan::Category::Border => 1,
+        fuzzy_dbscan::Category::Noise => 2,
+        fuzzy_dbscan::Category::Reassigned => 3,
+    }
+}
+
+fn u16_vec_into_bytes(out: Vec<u16>) -> Vec<u8> {
+    // Vec::into_raw_parts is unstable, so we'll just do this
+    let (ptr, len, cap) = (out.as_ptr(), out.len(), out.capacity());
+    std::mem::forget(out);
+
+    // SAFETY: u16 is double the size of u8, so this should be fine
+    unsafe { Vec::from_raw_parts(ptr as *mut u8, len * 2, cap * 2) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fuzzy_dbscan::FuzzyDBSCAN;
+    use std::collections::HashSet;
+
+    fn to_words(bytes: &[u8]) -> Vec<u16> {
+        bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn header_counts_match_assignments() {
+        let data: Vec<PointScalar> = vec![
+            0.0, 0.0, 0.1, 0.0, 0.0, 0.1, // first cluster
+            5.0, 5.0, 5.1, 5.0, 5.0, 5.1, // second cluster
+            20.0, 20.0, // noise
+        ];
+        let bytes: Vec<u8> = data.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let points = read_packed_data_dyn(&bytes, 2).unwrap();
+        let clusters = FuzzyDBSCAN {
+            eps_min: 0.2,
+            eps_max: 0.5,
+            pts_min: 2.0,
+            pts_max: 3.0,
+        }
+        .cluster(&points);
+
+        let words = to_words(
+            &pack_clusters_with_header(&clusters, 2, points.len(), LabelBits::Sixteen).unwrap(),
+        );
+        assert_eq!(words[0], FORMAT_VERSION);
+        assert_eq!(words[2], 2);
+        let point_count = words[3] as usize | (words[4] as usize) << 16;
+        let cluster_count = words[5] as usize | (words[6] as usize) << 16;
+
+        let mut cursor = HEADER_WORDS;
+        let mut walked_clusters = 0;
+        let mut indices = HashSet::new();
+        while cursor < words.len() {
+            let count = words[cursor] as usize;
+            for i in 0..count {
+                indices.insert(words[cursor + 1 + i * 2] & 0x3FFF);
+            }
+            cursor += 1 + count * 2;
+            walked_clusters += 1;
+        }
+        assert_eq!(cursor, words.len());
+        assert_eq!(cluster_count, walked_clusters);
+        assert_eq!(cluster_count, 3);
+        assert_eq!(point_count, indices.len());
+        assert_eq!(point_count, 7);
+    }
+
+    #[test]
+    fn omitting_noise_only_lists_cluster_members() {
+        // one tight cluster of 3 points among 40 scattered noise points
+        let mut data: Vec<PointScalar> = vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1];
+        for i in 0..40 {
+            data.extend([10.0 + i as PointScalar * 3.0, 10.0]);
+        }
+        let bytes: Vec<u8> = data.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let points = read_packed_data_dyn(&bytes, 2).unwrap();
+        let clusters = FuzzyDBSCAN {
+            eps_min: 0.2,
+            eps_max: 0.5,
+            pts_min: 2.0,
+            pts_max: 3.0,
+        }
+        .cluster(&points);
+        assert!(crate::analysis::is_noise(clusters.last().unwrap()));
+
+        let words = to_words(
+            &pack_clusters_without_noise(&clusters, 2, points.len(), LabelBits::Sixteen).unwrap(),
+        );
+        assert_eq!(words[1], FLAG_NOISE_OMITTED);
+        assert_eq!(words[3] as usize | (words[4] as usize) << 16, 43);
+        assert_eq!(words[5] as usize | (words[6] as usize) << 16, 1);
+        let listed: HashSet<u16> = (0..words[HEADER_WORDS] as usize)
+            .map(|i| words[HEADER_WORDS + 1 + i * 2] & 0x3FFF)
+            .collect();
+        assert_eq!(listed, HashSet::from([0, 1, 2]));
+        assert_eq!(
+            words.len(),
+            HEADER_WORDS + 1 + words[HEADER_WORDS] as usize * 2
+        );
+
+        let full = to_words(
+            &pack_clusters_with_header(&clusters, 2, points.len(), LabelBits::Sixteen).unwrap(),
+        );
+        assert!(words.len() * 4 < full.len());
+    }
+
+    #[test]
+    fn eight_bit_labels_follow_the_indices() {
+        let assignment = |index, label| fuzzy_dbscan::Assignment {
+            index,
+            label,
+            category: fuzzy_dbscan::Category::Border,
+            core_distance: None,
+            core_index: None,
+        };
+        let clusters = vec![
+            vec![assignment(4, 1.0), assignment(7, 0.5), assignment(9, 0.0)],
+            vec![assignment(2, 0.25), assignment(3, 0.75)],
+        ];
+        let words =
+            to_words(&pack_clusters_with_header(&clusters, 2, 10, LabelBits::Eight).unwrap());
+        assert_eq!(words[1], FLAG_8_BIT_LABELS);
+        let body = &words[HEADER_WORDS..];
+        // count, 3 index words, 2 words for 3 labels and padding, then count, 2 index words, 1 label word
+        assert_eq!(body.len(), 1 + 3 + 2 + 1 + 2 + 1);
+        assert_eq!(body[0], 3);
+        assert_eq!(body[1..4], [4 | 1 << 14, 7 | 1 << 14, 9 | 1 << 14]);
+        assert_eq!(body[4].to_le_bytes(), [255, 127]);
+        assert_eq!(body[5].to_le_bytes(), [0, 0]);
+        assert_eq!(body[6], 2);
+        assert_eq!(body[9].to_le_bytes(), [63, 191]);
+    }
+
+    #[test]
+    fn header_formats_reject_indices_beyond_14_bits() {
+        let assignment = |index| fuzzy_dbscan::Assignment {
+            index,
+            label: 1.0,
+            category: fuzzy_dbscan::Category::Core,
+            core_distance: None,
+            core_index: None,
+        };
+        let last = MAX_INDEXED_POINTS - 1;
+        let clusters = vec![vec![assignment(0), assignment(last)]];
+        assert!(pack_clusters_with_header(&clusters, 1, last + 1, LabelBits::Sixteen).is_ok());
+
+        let clusters = vec![vec![assignment(0), assignment(last + 1)]];
+        for label_bits in [LabelBits::Sixteen, LabelBits::Eight] {
+            assert!(matches!(
+                pack_clusters_with_header(&clusters, 1, last + 2, label_bits),
+                Err(ClusterError::IndexExceedsFormat { index, .. }) if index == last + 1
+            ));
+        }
+        assert!(matches!(
+            pack_clusters_without_noise(&clusters, 1, last + 2, LabelBits::Sixteen),
+            Err(ClusterError::IndexExceedsFormat { .. })
+        ));
+    }
+}
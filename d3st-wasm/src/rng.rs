@@ -0,0 +1,40 @@
+//! Small seeded pseudo-random number generator.
+//!
+//! We don't need cryptographic quality, only results that are reproducible from a seed on every platform,
+//! so this is a plain SplitMix64.
+
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed index in `0..n`.
+    #[cfg(feature = "wasm")]
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a uniformly distributed number in `0..1`.
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a standard normally distributed number (Box-Muller transform).
+    pub fn gaussian(&mut self) -> f64 {
+        // 1 - uniform is in 0..=1 minus 0, so the logarithm is finite
+        let radius = (-2. * (1. - self.uniform()).ln()).sqrt();
+        radius * (std::f64::consts::TAU * self.uniform()).cos()
+    }
+}
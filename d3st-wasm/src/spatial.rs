@@ -0,0 +1,66 @@
+//! Spatial orderings of points.
+
+use crate::packing::PointScalar;
+use crate::ClusterError;
+
+/// Largest number of dimensions [`morton_order`] supports.
+pub const MORTON_MAX_DIMENSIONS: usize = 3;
+
+/// Scales each coordinate of packed points to `0..=1` by the minimum and maximum of its dimension.
+///
+/// Dimensions with a single value map to 0, and non-finite coordinates are ignored for the range and map to 0.
+pub fn normalize_coordinates(data: &[PointScalar], dimensions: usize) -> Vec<f64> {
+    let mut ranges = vec![(f64::INFINITY, f64::NEG_INFINITY); dimensions];
+    for point in data.chunks_exact(dimensions) {
+        for (range, &value) in ranges.iter_mut().zip(point) {
+            if value.is_finite() {
+                range.0 = range.0.min(value as f64);
+                range.1 = range.1.max(value as f64);
+            }
+        }
+    }
+    data.chunks_exact(dimensions)
+        .flat_map(|point| point.iter().zip(&ranges))
+        .map(|(&value, &(min, max))| {
+            if value.is_finite() && max > min {
+                (value as f64 - min) / (max - min)
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
+
+/// Returns the indices of packed points with up to [`MORTON_MAX_DIMENSIONS`] dimensions sorted along the Morton
+/// (Z-order) curve, so points that are close in space tend to be close in the order.
+///
+/// Coordinates are [normalized](normalize_coordinates) per dimension and quantized to `64 / dimensions` bits
+/// (at most 63, and 21 in 3D), whose bits are then interleaved with the first dimension in the lowest bit.
+/// Points with the same code keep their input order.
+pub fn morton_order(data: &[PointScalar], dimensions: usize) -> Result<Vec<usize>, ClusterError> {
+    if dimensions == 0 || dimensions > MORTON_MAX_DIMENSIONS {
+        return Err(ClusterError::UnsupportedDimensions {
+            dimensions,
+            max: MORTON_MAX_DIMENSIONS,
+        });
+    }
+    let bits = 64 / dimensions as u32;
+    let scale = ((1u64 << bits.min(63)) - 1) as f64;
+    let normalized = normalize_coordinates(data, dimensions);
+    let codes: Vec<u64> = normalized
+        .chunks_exact(dimensions)
+        .map(|point| {
+            let mut code = 0u64;
+            for (dim, &value) in point.iter().enumerate() {
+                let quantized = (value * scale) as u64;
+                for bit in 0..bits {
+                    code |= ((quantized >> bit) & 1) << (bit as usize * dimensions + dim);
+                }
+            }
+            code
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..codes.len()).collect();
+    order.sort_by_key(|&index| codes[index]);
+    Ok(order)
+}
@@ -0,0 +1,154 @@
+//! Transformations applied to points before clustering.
+
+use crate::packing::PointScalar;
+use crate::rng::Rng;
+
+/// A fixed random linear projection to fewer dimensions.
+///
+/// Distances between points concentrate in many dimensions, which makes density thresholds hard to choose.
+/// Projecting onto random Gaussian directions roughly preserves pairwise distances (Johnson-Lindenstrauss) at a
+/// small fraction of the cost of PCA. The matrix is generated from `seed`, so the same seed always yields the
+/// same projection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomProjection {
+    pub target_dimensions: usize,
+    pub seed: u64,
+}
+
+impl RandomProjection {
+    /// Returns the row-major `target_dimensions × dimensions` projection matrix.
+    ///
+    /// Entries are drawn from a normal distribution with variance `1 / target_dimensions`, so projected
+    /// distances are on the same scale as the original ones and `eps` values carry over.
+    pub fn matrix(&self, dimensions: usize) -> Vec<PointScalar> {
+        let mut rng = Rng::new(self.seed);
+        let scale = (self.target_dimensions as f64).sqrt().recip();
+        (0..self.target_dimensions * dimensions)
+            .map(|_| (rng.gaussian() * scale) as PointScalar)
+            .collect()
+    }
+
+    /// Projects packed points with `dimensions` scalars each, returning packed points with `target_dimensions`
+    /// scalars each in the same order. Trailing scalars that don't form a whole point are ignored.
+    pub fn apply(&self, data: &[PointScalar], dimensions: usize) -> Vec<PointScalar> {
+        let matrix = self.matrix(dimensions);
+        let mut projected = Vec::with_capacity(data.len() / dimensions * self.target_dimensions);
+        for point in data.chunks_exact(dimensions) {
+            for row in matrix.chunks_exact(dimensions) {
+                projected.push(row.iter().zip(point).map(|(r, x)| r * x).sum());
+            }
+        }
+        projected
+    }
+}
+
+/// How each dimension is centered and scaled before clustering, so that dimensions with different units count
+/// alike and one eps fits all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    /// Subtracts the mean and divides by the standard deviation.
+    ZScore,
+    /// Maps the range of each dimension to 0 to 1.
+    MinMax,
+    /// Subtracts the median and divides by the interquartile range, which outliers barely move, unlike the mean
+    /// and standard deviation or the range.
+    Robust,
+}
+
+impl Scaling {
+    /// Computes the center and scale of each dimension of packed points with `dimensions` scalars each.
+    ///
+    /// Non-finite coordinates are left out of the statistics. A dimension without spread (e.g. an IQR of 0 for
+    /// robust scaling) is only centered, not scaled.
+    pub fn fit(&self, data: &[PointScalar], dimensions: usize) -> FittedScaling {
+        let mut centers = Vec::with_capacity(dimensions);
+        let mut scales = Vec::with_capacity(dimensions);
+        for dim in 0..dimensions {
+            let mut values: Vec<f64> = data
+                .iter()
+                .skip(dim)
+                .step_by(dimensions)
+                .map(|&value| value as f64)
+                .filter(|value| value.is_finite())
+                .collect();
+            let (center, scale) = if values.is_empty() {
+                (0., 1.)
+            } else {
+                match self {
+                    Scaling::ZScore => {
+                        let n = values.len() as f64;
+                        let mean = values.iter().sum::<f64>() / n;
+                        let variance = values
+                            .iter()
+                            .map(|value| (value - mean).powi(2))
+                            .sum::<f64>()
+                            / n;
+                        (mean, variance.sqrt())
+                    }
+                    Scaling::MinMax => {
+                        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                        (min, max - min)
+                    }
+                    Scaling::Robust => {
+                        let median = quantile(&mut values, 0.5);
+                        let iqr = quantile(&mut values, 0.75) - quantile(&mut values, 0.25);
+                        (median, iqr)
+                    }
+                }
+            };
+            centers.push(center as PointScalar);
+            scales.push(if scale > 0. { scale as PointScalar } else { 1. });
+        }
+        FittedScaling { centers, scales }
+    }
+}
+
+/// The value at rank `q * (len - 1)` (rounded) of `values`, which are reordered. Uses a quickselect, so it takes
+/// linear time.
+fn quantile(values: &mut [f64], q: f64) -> f64 {
+    let rank = (q * (values.len() - 1) as f64).round() as usize;
+    *values.select_nth_unstable_by(rank, f64::total_cmp).1
+}
+
+/// Per-dimension centers and scales computed by [`Scaling::fit`], which can be applied to more points, e.g.
+/// queries against a clustering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FittedScaling {
+    pub centers: Vec<PointScalar>,
+    pub scales: Vec<PointScalar>,
+}
+
+impl FittedScaling {
+    /// Centers and scales packed points with one scalar per fitted dimension in place.
+    pub fn apply(&self, data: &mut [PointScalar]) {
+        if self.centers.is_empty() {
+            return;
+        }
+        for point in data.chunks_exact_mut(self.centers.len()) {
+            for ((value, center), scale) in point.iter_mut().zip(&self.centers).zip(&self.scales) {
+                *value = (*value - center) / scale;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn robust_scaling_ignores_outliers() {
+        // one dimension with a wild outlier, one constant dimension
+        let data: Vec<PointScalar> = [1., 2., 3., 4., 5., 1000.]
+            .iter()
+            .flat_map(|&x| [x, 7.])
+            .collect();
+        let fitted = Scaling::Robust.fit(&data, 2);
+        assert_eq!(fitted.centers, [4., 7.]);
+        assert_eq!(fitted.scales, [3., 1.]);
+        let mut scaled = data.clone();
+        fitted.apply(&mut scaled);
+        assert_eq!(&scaled[..4], [-1., 0., -2. / 3., 0.]);
+    }
+}
@@ -0,0 +1,4080 @@
+//! JavaScript bindings.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::fuzzy_dbscan::{MetricSpace, SpatialIndex};
+use crate::metric::{metric_points, Metric, MetricPoint};
+use crate::packing::{
+    byte_array_as_scalar_type, category_code, half_byte_array_as_scalars, pack_clusters,
+    pack_clusters_into_slice, pack_clusters_with_header, pack_clusters_without_noise, packed_size,
+    read_interleaved, scalars_as_points, scalars_as_points_dyn, LabelBits, PointScalar,
+};
+use crate::transform::{FittedScaling, RandomProjection, Scaling};
+use crate::{analysis, diagnostics, fuzzy_dbscan, graph, rng, spatial, ClusterError};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+/// FuzzyDBSCAN clustering exposed to JavaScript.
+///
+/// # Sharing
+/// `FuzzyCluster` is `Send + Sync` and holds no interior mutability. Methods taking `&self` only read its state
+/// and may be called concurrently on an instance shared behind an `Arc`; methods taking `&mut self` modify it
+/// and need exclusive access.
+///
+/// Setters such as `set_sample_every` change the parameters. Clustering methods such as `cluster` keep the input,
+/// result and warnings of the run for follow-up queries (such as `last_run_warnings` or `medoids`), so they take
+/// `&mut self` and can't run concurrently; the follow-up queries only read. For concurrent work, set the points
+/// once with `set_points` and share the instance: `recluster_with` clusters them with its own parameters and
+/// returns its warnings with the result instead of keeping them, and `neighbors_of` only reads.
+#[wasm_bindgen]
+pub struct FuzzyCluster {
+    inner: fuzzy_dbscan::FuzzyDBSCAN,
+    sample: Option<Sample>,
+    exclusion_radius: f64,
+    max_clusters: Option<usize>,
+    /// Per-point timestamps for `time_window`, see `set_timestamps`.
+    timestamps: Vec<f64>,
+    /// Inclusive range of timestamps of the points that are clustered.
+    time_window: Option<(f64, f64)>,
+    /// External ids of the input points, see `set_point_ids`.
+    point_ids: Option<Vec<i64>>,
+    /// Per-point weights for densities, see `set_point_weights`; empty if every point counts once.
+    weights: Vec<f32>,
+    /// Whether each input point has a trailing weight column, see `set_embedded_weights`.
+    embedded_weights: bool,
+    /// Bytes a clustering may use, see `set_memory_budget`.
+    memory_budget: Option<usize>,
+    /// `(point index, hint)` pairs that seed clusters, see `set_seeds`.
+    seeds: Vec<(usize, u32)>,
+    /// Indices of points that may not become core points, see `set_excluded_cores`.
+    excluded_cores: Vec<usize>,
+    /// Pairs of points that must share a cluster, see `set_must_links`.
+    must_links: Vec<(usize, usize)>,
+    /// Pairs of points that must not share a cluster, see `set_cannot_links`.
+    cannot_links: Vec<(usize, usize)>,
+    /// Stride and offset in scalars of the coordinates in interleaved input, see `set_interleaved`.
+    interleaving: Option<(usize, usize)>,
+    /// Whether input holds 16-bit floats, see `set_half_precision`.
+    half_precision: bool,
+    projection: Option<RandomProjection>,
+    /// Whether the metric treats the last dimension as a category, see `set_categorical_last_dimension`.
+    categorical_last_dimension: bool,
+    /// How dimensions are scaled before the projection, see `set_robust_scaling`.
+    scaling: Option<Scaling>,
+    metric: Metric,
+    /// Metrics of an ensemble clustering, see `add_ensemble_metric`.
+    ensemble_metrics: Vec<Metric>,
+    /// Label below which core points are categorized as border points, see `set_core_threshold`.
+    core_threshold: f64,
+    /// Mean label below which clusters are dissolved into noise, see `set_min_avg_membership`.
+    min_avg_membership: f64,
+    /// Whether border labels are scaled by the local density, see `set_density_weighted_labels`.
+    density_weighted_labels: bool,
+    /// Factor of `eps_max` within which noise is attached to the nearest cluster, see `set_noise_reassignment`.
+    noise_reassignment: Option<f64>,
+    /// Whether invalid parameters are fixed with a warning instead of failing the clustering.
+    tolerant: bool,
+    /// Width of the labels in results with a header, see `set_label_bits`.
+    label_bits: LabelBits,
+    /// Whether results always end with a (possibly empty) noise group, see `set_compact_noise`.
+    compact_noise: bool,
+    /// Seed for breaking ties between equal memberships randomly, see `set_random_tie_break`.
+    tie_break_seed: Option<u32>,
+    /// Non-fatal warnings from the most recent clustering.
+    warnings: Vec<String>,
+    last_run: Option<LastRun>,
+    /// Clustering that is advanced in steps, see `start_clustering`.
+    session: Option<Session>,
+    previous: Option<PreviousClusters>,
+    /// Whether `cluster_ids` matches previous clusters optimally, see `set_optimal_cluster_matching`.
+    optimal_matching: bool,
+    /// Points set with `set_points`, for `recluster` and `neighbors_of`.
+    points: Option<IndexedPoints>,
+}
+
+/// Points set with `set_points`, prepared for clustering, with a spatial index of them.
+struct IndexedPoints {
+    data: Vec<PointScalar>,
+    dimensions: usize,
+    metric: Metric,
+    /// The scaling fitted to the input, which queries are scaled with.
+    scaling: Option<FittedScaling>,
+    /// Warnings about the input, which every `recluster` reports again.
+    warnings: Vec<String>,
+    /// Spatial index of `data` measured with `metric`.
+    index: Arc<SpatialIndex>,
+}
+
+/// Clusters of a previous frame whose ids are reused by `cluster_ids`.
+struct PreviousClusters {
+    /// Packed centroids with the dimensions of the clustered points.
+    centroids: Vec<PointScalar>,
+    ids: Vec<u32>,
+    max_distance: f64,
+}
+
+/// Input and result of the most recent clustering, kept for follow-up queries.
+///
+/// `data` holds the points as they were clustered, i.e. after scaling and a random projection.
+struct LastRun {
+    data: Vec<PointScalar>,
+    dimensions: usize,
+    metric: Metric,
+    /// Parameters after resolving invalid ones and tuning eps.
+    parameters: fuzzy_dbscan::FuzzyDBSCAN,
+    clusters: Vec<fuzzy_dbscan::Cluster>,
+    /// The clusters before `finish_clusters`, if `update_points` can update them incrementally.
+    raw_clusters: Option<Vec<fuzzy_dbscan::Cluster>>,
+    /// The scaling fitted to the input, which queries are scaled with.
+    scaling: Option<FittedScaling>,
+    /// Spatial index of `data` measured with `metric`, if the points were set with `set_points` or updated
+    /// incrementally by `update_points`.
+    index: Option<Arc<SpatialIndex>>,
+}
+
+impl LastRun {
+    fn point_count(&self) -> usize {
+        self.data.len() / self.dimensions
+    }
+
+    fn points(&self) -> Vec<MetricPoint<'_>> {
+        metric_points(&self.data, self.dimensions, &self.metric)
+    }
+}
+
+/// A clustering in progress that is advanced in steps.
+struct Session {
+    data: Vec<PointScalar>,
+    dimensions: usize,
+    metric: Metric,
+    dbscan: fuzzy_dbscan::FuzzyDBSCAN,
+    state: fuzzy_dbscan::ClusterState,
+    /// The scaling fitted to the input; not serialized.
+    scaling: Option<FittedScaling>,
+}
+
+impl Session {
+    /// Serializes the points and clustering state (not the parameters) into a little-endian byte buffer.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.data.len() * 4);
+        bytes.extend_from_slice(&(self.dimensions as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.data.len() as u32).to_le_bytes());
+        for value in &self.data {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend(self.state.to_bytes());
+        bytes
+    }
+
+    /// Reads the points and clustering state written by `to_bytes`.
+    fn read_bytes(
+        bytes: &[u8],
+    ) -> Result<(Vec<PointScalar>, usize, fuzzy_dbscan::ClusterState), ClusterError> {
+        let read_u32 = |offset: usize| -> Result<usize, ClusterError> {
+            let word = bytes
+                .get(offset..offset + 4)
+                .ok_or(ClusterError::InvalidState)?;
+            Ok(u32::from_le_bytes(word.try_into().unwrap()) as usize)
+        };
+        let dimensions = read_u32(0)?;
+        let scalar_count = read_u32(4)?;
+        let data_end = 8 + scalar_count * 4;
+        let data: Vec<PointScalar> = bytes
+            .get(8..data_end)
+            .ok_or(ClusterError::InvalidState)?
+            .chunks_exact(4)
+            .map(|scalar| PointScalar::from_le_bytes(scalar.try_into().unwrap()))
+            .collect();
+        let state = fuzzy_dbscan::ClusterState::from_bytes(&bytes[data_end..])?;
+        if dimensions == 0 || state.point_count() * dimensions != data.len() {
+            return Err(ClusterError::InvalidState);
+        }
+        Ok((data, dimensions, state))
+    }
+}
+
+// FuzzyCluster must stay shareable between workers for read-only queries.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<FuzzyCluster>();
+};
+
+/// Number of bisection steps when tuning eps for a maximum cluster count, see `set_max_clusters`.
+const EPS_SEARCH_STEPS: usize = 12;
+
+/// Selects the subset of points that is clustered, e.g. for a quick preview.
+#[derive(Debug, Clone, Copy)]
+enum Sample {
+    /// Every n-th point, starting with the first.
+    Every(usize),
+    /// A seeded uniform random sample of the given size.
+    Random { count: usize, seed: u32 },
+}
+
+impl Sample {
+    /// Returns the sorted original indices of the sampled points.
+    fn indices(&self, point_count: usize) -> Vec<usize> {
+        match *self {
+            Sample::Every(step) => (0..point_count).step_by(step.max(1)).collect(),
+            Sample::Random { count, seed } => {
+                let mut indices: Vec<usize> = (0..point_count).collect();
+                let count = count.min(point_count);
+                let mut rng = rng::Rng::new(seed as u64);
+                // partial Fisher-Yates shuffle
+                for i in 0..count {
+                    let j = i + rng.below(point_count - i);
+                    indices.swap(i, j);
+                }
+                indices.truncate(count);
+                indices.sort_unstable();
+                indices
+            }
+        }
+    }
+}
+
+/// Zips point indices from JavaScript into pairs, failing if the arrays have different lengths.
+fn index_pairs(a: Vec<u32>, b: Vec<u32>) -> Result<Vec<(usize, usize)>, ClusterError> {
+    if a.len() != b.len() {
+        return Err(ClusterError::LengthMismatch {
+            a: a.len(),
+            b: b.len(),
+        });
+    }
+    Ok(a.into_iter()
+        .zip(b)
+        .map(|(a, b)| (a as usize, b as usize))
+        .collect())
+}
+
+/// Groups each of the points at `indices` with the first earlier point within `radius`.
+///
+/// Returns groups of original indices; the first index of every group is its representative.
+fn collapse_near_duplicates<P: fuzzy_dbscan::MetricSpace>(
+    points: &[P],
+    indices: &[usize],
+    radius: f64,
+) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for &index in indices {
+        let representative = groups
+            .iter_mut()
+            .find(|group| points[group[0]].distance(&points[index]) <= radius);
+        match representative {
+            Some(group) => group.push(index),
+            None => groups.push(vec![index]),
+        }
+    }
+    groups
+}
+
+/// Evaluates `$body` with `$points` bound to `data` split into points of `dimensions` dimensions that are measured
+/// with `$metric`. Uses const implementations of the Euclidean metric for up to 7 dimensions.
+macro_rules! with_points {
+    ($metric:expr, $data:expr, $dimensions:expr, |$points:ident| $body:expr) => {{
+        match $metric {
+            Metric::Euclidean => match $dimensions {
+                1 => {
+                    let $points = &scalars_as_points::<1>($data)?[..];
+                    $body
+                }
+                2 => {
+                    let $points = &scalars_as_points::<2>($data)?[..];
+                    $body
+                }
+                3 => {
+                    let $points = &scalars_as_points::<3>($data)?[..];
+                    $body
+                }
+                4 => {
+                    let $points = &scalars_as_points::<4>($data)?[..];
+                    $body
+                }
+                5 => {
+                    let $points = &scalars_as_points::<5>($data)?[..];
+                    $body
+                }
+                6 => {
+                    let $points = &scalars_as_points::<6>($data)?[..];
+                    $body
+                }
+                7 => {
+                    let $points = &scalars_as_points::<7>($data)?[..];
+                    $body
+                }
+                _ => {
+                    let $points = &scalars_as_points_dyn($data, $dimensions)?[..];
+                    $body
+                }
+            },
+            metric => {
+                metric.validate($dimensions)?;
+                let points: Vec<MetricPoint> = scalars_as_points_dyn($data, $dimensions)?
+                    .into_iter()
+                    .map(|point| MetricPoint {
+                        data: point.data,
+                        metric,
+                    })
+                    .collect();
+                let $points = &points[..];
+                $body
+            }
+        }
+    }};
+}
+
+/// A point that counts `weight` times towards densities, see `set_point_weights`.
+#[derive(Clone, Copy)]
+struct Weighted<P> {
+    point: P,
+    weight: f64,
+}
+
+impl<P: MetricSpace> MetricSpace for Weighted<P> {
+    fn distance(&self, other: &Self) -> f64 {
+        self.point.distance(&other.point)
+    }
+
+    fn distance_within(&self, other: &Self, threshold: f64) -> Option<f64> {
+        self.point.distance_within(&other.point, threshold)
+    }
+
+    fn line_position(&self) -> Option<f64> {
+        self.point.line_position()
+    }
+
+    fn coordinates(&self) -> Option<Vec<f64>> {
+        self.point.coordinates()
+    }
+
+    fn weight(&self) -> f64 {
+        self.weight
+    }
+}
+
+/// Pairs each of `points` with its weight, or 1 if `weights` is empty.
+fn with_weights<P: Copy>(points: &[P], weights: &[f32]) -> Vec<Weighted<P>> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(index, &point)| Weighted {
+            point,
+            weight: weights.get(index).map_or(1.0, |&weight| weight as f64),
+        })
+        .collect()
+}
+
+/// Checks that `weights` are finite and non-negative.
+fn check_weights(weights: &[f32]) -> Result<(), ClusterError> {
+    match weights
+        .iter()
+        .position(|weight| !weight.is_finite() || *weight < 0.)
+    {
+        Some(index) => Err(ClusterError::InvalidWeight {
+            index,
+            value: weights[index],
+        }),
+        None => Ok(()),
+    }
+}
+
+impl FuzzyCluster {
+    /// Pairs each of `points` with its weight (see `set_point_weights`), or 1 without weights.
+    fn weighted<P: Copy>(&self, points: &[P]) -> Vec<Weighted<P>> {
+        with_weights(points, &self.weights)
+    }
+
+    /// Clusters the sampled subset of `points` (or all of them) within the time window, with indices referring to
+    /// `points`.
+    ///
+    /// With an exclusion radius, near-duplicates are clustered as one representative and every original point
+    /// receives the assignments of its representative.
+    fn cluster_points<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        points: &[P],
+        index: Option<&SpatialIndex>,
+    ) -> Vec<fuzzy_dbscan::Cluster> {
+        self.cluster_points_levels(std::slice::from_ref(dbscan), points, index)
+            .pop()
+            .unwrap()
+    }
+
+    /// Returns the indices of the points out of `point_count` that `cluster_points` clusters: the sample (or all
+    /// points) within the time window.
+    fn clustered_indices(&self, point_count: usize) -> Vec<usize> {
+        let mut indices = match self.sample {
+            Some(sample) => sample.indices(point_count),
+            None => (0..point_count).collect(),
+        };
+        if let Some((start, end)) = self.time_window {
+            // `check_point_options` rejects missing timestamps
+            indices.retain(|&index| {
+                self.timestamps
+                    .get(index)
+                    .is_some_and(|timestamp| (start..=end).contains(timestamp))
+            });
+        }
+        indices
+    }
+
+    /// Whether `cluster_points` clusters only some of the points: a sample, those within the time window or one
+    /// of each group of near-duplicates.
+    fn clusters_subset(&self) -> bool {
+        self.sample.is_some() || self.exclusion_radius > 0.0 || self.time_window.is_some()
+    }
+
+    /// Groups the points out of `points` that `cluster_points` clusters (see `clustered_indices`) with their
+    /// near-duplicates, if any. The first index of every group is the point that is clustered for all of them.
+    fn clustered_groups<P: fuzzy_dbscan::MetricSpace>(&self, points: &[P]) -> Vec<Vec<usize>> {
+        let indices = self.clustered_indices(points.len());
+        if self.exclusion_radius > 0.0 {
+            collapse_near_duplicates(points, &indices, self.exclusion_radius)
+        } else {
+            indices.into_iter().map(|index| vec![index]).collect()
+        }
+    }
+
+    /// Clusters like `cluster_points` once per parameters of `levels`, sharing the neighborhood search.
+    ///
+    /// `index`, if any, is a spatial index of all `points`, which is used unless a sample, near-duplicates or a
+    /// time window leave out points.
+    fn cluster_points_levels<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        levels: &[fuzzy_dbscan::FuzzyDBSCAN],
+        points: &[P],
+        index: Option<&SpatialIndex>,
+    ) -> Vec<Vec<fuzzy_dbscan::Cluster>> {
+        let points = &self.weighted(points)[..];
+        if !self.clusters_subset() {
+            let state = self.initial_state(points.len(), Some);
+            return self.cluster_state_levels(levels, points, &state, index);
+        }
+        let groups = self.clustered_groups(points);
+        let subset: Vec<_> = groups.iter().map(|group| points[group[0]]).collect();
+        let positions: HashMap<usize, usize> = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(position, group)| group.iter().map(move |&index| (index, position)))
+            .collect();
+        let state = self.initial_state(subset.len(), |index| positions.get(&index).copied());
+        self.cluster_state_levels(levels, &subset, &state, None)
+            .into_iter()
+            .map(|clusters| {
+                clusters
+                    .into_iter()
+                    .map(|cluster| {
+                        cluster
+                            .into_iter()
+                            .flat_map(|assignment| {
+                                groups[assignment.index].iter().map(move |&index| {
+                                    fuzzy_dbscan::Assignment {
+                                        index,
+                                        ..assignment.clone()
+                                    }
+                                })
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Clusters `points` from `state` once per parameters of `levels`, with a spatial `index` of them if any.
+    ///
+    /// Sharing the neighborhood search between levels caches the neighborhoods of all points, which the memory
+    /// budget doesn't account for, so with a budget the levels are clustered one at a time instead.
+    fn cluster_state_levels<P: fuzzy_dbscan::MetricSpace>(
+        &self,
+        levels: &[fuzzy_dbscan::FuzzyDBSCAN],
+        points: &[P],
+        state: &fuzzy_dbscan::ClusterState,
+        index: Option<&SpatialIndex>,
+    ) -> Vec<Vec<fuzzy_dbscan::Cluster>> {
+        if self.memory_budget.is_some() && levels.len() > 1 {
+            return levels
+                .iter()
+                .flat_map(|level| {
+                    self.cluster_state_levels(std::slice::from_ref(level), points, state, index)
+                })
+                .collect();
+        }
+        match index {
+            Some(index) => {
+                fuzzy_dbscan::FuzzyDBSCAN::cluster_levels_indexed(levels, points, state, index)
+            }
+            None => fuzzy_dbscan::FuzzyDBSCAN::cluster_levels(levels, points, state),
+        }
+    }
+
+    /// Clusters like `cluster_points`, but with a maximum cluster count raises eps (keeping the ratio of `eps_min`
+    /// to `eps_max`) until the points form at most that many clusters. Returns the clusters and the parameters
+    /// that produced them.
+    ///
+    /// The search is a bisection between the configured eps and the extent of the data. If even the largest eps
+    /// produces too many clusters, the smallest ones are turned into noise instead.
+    fn cluster_limited<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        points: &[P],
+        index: Option<&SpatialIndex>,
+    ) -> (Vec<fuzzy_dbscan::Cluster>, fuzzy_dbscan::FuzzyDBSCAN) {
+        let clusters = self.cluster_points(dbscan, points, index);
+        let Some(max_clusters) = self.max_clusters else {
+            return (clusters, dbscan.clone());
+        };
+        let count = |clusters: &[fuzzy_dbscan::Cluster]| {
+            clusters
+                .iter()
+                .filter(|cluster| !analysis::is_noise(cluster))
+                .count()
+        };
+        if count(&clusters) <= max_clusters || points.is_empty() {
+            return (clusters, dbscan.clone());
+        }
+        let scaled = |scale: f64| fuzzy_dbscan::FuzzyDBSCAN {
+            eps_min: dbscan.eps_min * scale,
+            eps_max: dbscan.eps_max * scale,
+            ..dbscan.clone()
+        };
+        // by the triangle inequality, no two points are farther apart than twice the farthest from the first one
+        let extent = 2.
+            * points
+                .iter()
+                .map(|point| points[0].distance(point))
+                .filter(|distance| distance.is_finite())
+                .fold(0., f64::max);
+        if dbscan.eps_max > 0. && extent > dbscan.eps_max {
+            let (mut low, mut high) = (1., extent / dbscan.eps_max);
+            let mut best = self.cluster_points(&scaled(high), points, index);
+            if count(&best) <= max_clusters {
+                for _ in 0..EPS_SEARCH_STEPS {
+                    let middle = (low + high) / 2.;
+                    let clusters = self.cluster_points(&scaled(middle), points, index);
+                    if count(&clusters) <= max_clusters {
+                        high = middle;
+                        best = clusters;
+                    } else {
+                        low = middle;
+                    }
+                }
+                return (best, scaled(high));
+            }
+        }
+        (
+            analysis::keep_largest(clusters, max_clusters),
+            dbscan.clone(),
+        )
+    }
+
+    /// Measures distances with `metric`, over all but the last dimension if that is categorical.
+    fn use_metric(&mut self, metric: Metric) {
+        self.metric = if self.categorical_last_dimension {
+            Metric::Categorical {
+                inner: Box::new(metric),
+            }
+        } else {
+            metric
+        };
+    }
+
+    /// Returns the parameters to cluster the points at `clustered` with, after fixing or rejecting invalid ones
+    /// depending on the mode.
+    fn resolve_parameters(
+        &mut self,
+        clustered: &[usize],
+    ) -> Result<fuzzy_dbscan::FuzzyDBSCAN, ClusterError> {
+        self.resolve(self.inner.clone(), clustered)
+    }
+
+    /// Fixes or rejects invalid parameters for clustering the points at `clustered` depending on the mode, and
+    /// records warnings about fixed ones.
+    fn resolve(
+        &mut self,
+        dbscan: fuzzy_dbscan::FuzzyDBSCAN,
+        clustered: &[usize],
+    ) -> Result<fuzzy_dbscan::FuzzyDBSCAN, ClusterError> {
+        let mut warnings = Vec::new();
+        let dbscan = self.resolve_with(dbscan, clustered, &mut warnings);
+        self.warnings.extend(warnings);
+        dbscan
+    }
+
+    /// Fixes or rejects invalid parameters like `resolve`, but adds the warnings to `warnings`.
+    fn resolve_with(
+        &self,
+        mut dbscan: fuzzy_dbscan::FuzzyDBSCAN,
+        clustered: &[usize],
+        warnings: &mut Vec<String>,
+    ) -> Result<fuzzy_dbscan::FuzzyDBSCAN, ClusterError> {
+        if dbscan.eps_min > dbscan.eps_max {
+            if !self.tolerant {
+                return Err(ClusterError::InvertedEpsRange {
+                    eps_min: dbscan.eps_min,
+                    eps_max: dbscan.eps_max,
+                });
+            }
+            std::mem::swap(&mut dbscan.eps_min, &mut dbscan.eps_max);
+            warnings.push(format!(
+                "eps_min ({}) was greater than eps_max ({}) and they were swapped",
+                dbscan.eps_max, dbscan.eps_min
+            ));
+        }
+        // a point's density includes its own weight, so thresholds below the smallest weight would make every
+        // point a core point
+        let min_density = self
+            .weights
+            .iter()
+            .map(|&weight| weight as f64)
+            .reduce(f64::min)
+            .unwrap_or(1.0);
+        for (name, pts) in [
+            ("pts_min", &mut dbscan.pts_min),
+            ("pts_max", &mut dbscan.pts_max),
+        ] {
+            if *pts < min_density {
+                if !self.tolerant {
+                    return Err(ClusterError::PtsBelowMinDensity {
+                        name,
+                        value: *pts,
+                        min_density,
+                    });
+                }
+                warnings.push(format!(
+                    "{} ({}) was below {}, the smallest density of a point, and was set to it",
+                    name, *pts, min_density
+                ));
+                *pts = min_density;
+            }
+        }
+        // a point is a core point if its density exceeds pts_min or reaches pts_max, and the density is at most the
+        // total weight, which it only reaches if all points are within eps_min; a larger pts_max only caps labels
+        // below 1
+        let weight = |index: usize| self.weights.get(index).map_or(1.0, |&weight| weight as f64);
+        let total_weight: f64 = clustered.iter().map(|&index| weight(index)).sum();
+        if clustered.len() > 1 && dbscan.pts_min >= total_weight && dbscan.pts_max > total_weight {
+            if !self.tolerant {
+                return Err(ClusterError::ParameterExceedsData {
+                    value: dbscan.pts_min,
+                    total_weight,
+                });
+            }
+            // reached by the heaviest point if all others are within eps_min
+            let largest = clustered
+                .iter()
+                .map(|&index| weight(index))
+                .fold(0.0, f64::max);
+            let limit = total_weight - largest;
+            warnings.push(format!(
+                "pts_min ({}) was not below the total weight of the clustered points ({}) and was set to {}",
+                dbscan.pts_min, total_weight, limit
+            ));
+            dbscan.pts_min = limit;
+        }
+        Ok(dbscan)
+    }
+
+    /// Clusters data, records warnings about it and keeps the result as the most recent clustering.
+    ///
+    /// # Parameters
+    /// - packed_data: Float32Array of data point values
+    /// - dimensions: number of dimensions per data point
+    fn run(&mut self, packed_data: &[u8], dimensions: usize) -> Result<&LastRun, ClusterError> {
+        self.warnings.clear();
+        self.last_run = None;
+        let (data, dimensions, scaling) = self.prepare(packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&self.clustered_indices(data.len() / dimensions))?;
+        self.check_point_options(data.len() / dimensions)?;
+        let (clusters, parameters, raw_clusters) = if self.updatable() {
+            let (raw, clusters) = self.cluster_data_raw(&dbscan, &data, dimensions)?;
+            (clusters, dbscan, Some(raw))
+        } else {
+            let (clusters, parameters) =
+                self.cluster_data(&dbscan, &data, dimensions, true, None)?;
+            (clusters, parameters, None)
+        };
+        self.warnings.extend(diagnostics::noise_warning(&clusters));
+        Ok(self.last_run.insert(LastRun {
+            data,
+            dimensions,
+            metric: self.metric.clone(),
+            parameters,
+            clusters,
+            raw_clusters,
+            scaling,
+            index: None,
+        }))
+    }
+
+    /// Whether clusterings are plain [`cluster`](fuzzy_dbscan::FuzzyDBSCAN::cluster) runs before
+    /// `finish_clusters`, which `update_points` can update incrementally.
+    fn updatable(&self) -> bool {
+        self.sample.is_none()
+            && self.exclusion_radius <= 0.0
+            && self.time_window.is_none()
+            && self.max_clusters.is_none()
+            && self.seeds.is_empty()
+            && self.excluded_cores.is_empty()
+            && self.must_links.is_empty()
+            && self.cannot_links.is_empty()
+    }
+
+    /// Clusters prepared data like `cluster_data` without the cluster limit, and also returns the clusters
+    /// before `finish_clusters`.
+    fn cluster_data_raw(
+        &self,
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        data: &[PointScalar],
+        dimensions: usize,
+    ) -> Result<(Vec<fuzzy_dbscan::Cluster>, Vec<fuzzy_dbscan::Cluster>), ClusterError> {
+        Ok(with_points!(&self.metric, data, dimensions, |points| {
+            let raw = self.cluster_points(dbscan, points, None);
+            let mut clusters = raw.clone();
+            self.finish_clusters(points, dbscan, &mut clusters);
+            (raw, clusters)
+        }))
+    }
+
+    /// Moves the points at `changed` of the most recent clustering to the packed (or interleaved) points of
+    /// `packed_data`, in the same order, and clusters them again. Returns the points whose clusters changed.
+    fn update_points(
+        &mut self,
+        changed: &[u32],
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<u32>, ClusterError> {
+        let run = self.last_run()?;
+        let (moved, moved_weights) = self.read_weighted(packed_data, dimensions)?;
+        let (moved, dimensions) = self.transform(moved, dimensions, run.scaling.as_ref())?;
+        if dimensions != run.dimensions {
+            return Err(ClusterError::DimensionMismatch {
+                expected: run.dimensions,
+                got: dimensions,
+            });
+        }
+        if moved.len() / dimensions != changed.len() {
+            return Err(ClusterError::PointVectorLengthMismatch {
+                param_name: "changed_indices",
+                expected: moved.len() / dimensions,
+                got: changed.len(),
+            });
+        }
+        let point_count = run.point_count();
+        if let Some(&index) = changed.iter().find(|&&index| index as usize >= point_count) {
+            return Err(ClusterError::IndexOutOfRange {
+                index: index as usize,
+                point_count,
+            });
+        }
+        self.check_point_options(point_count)?;
+        check_weights(&moved_weights)?;
+
+        let mut run = self.last_run.take().expect("checked above");
+        // the last position of a point that is listed more than once wins
+        let moves: BTreeMap<usize, &[PointScalar]> = changed
+            .iter()
+            .map(|&index| index as usize)
+            .zip(moved.chunks_exact(dimensions))
+            .collect();
+        let previous_weights: Vec<f32> = if self.weights.is_empty() {
+            Vec::new()
+        } else {
+            moves.keys().map(|&index| self.weights[index]).collect()
+        };
+        if self.embedded_weights {
+            if self.weights.is_empty() {
+                self.weights = vec![1.0; point_count];
+            }
+            for (&index, weight) in changed.iter().zip(moved_weights) {
+                self.weights[index as usize] = weight;
+            }
+        }
+        // without weights before, the previous positions count once, which is also the default
+        let weights = if self.weights.is_empty() {
+            Vec::new()
+        } else {
+            [&self.weights[..], &previous_weights].concat()
+        };
+        let changed: Vec<usize> = moves.keys().copied().collect();
+        // a clustering of the points of `set_points` moves them too, so later reclusterings cluster the moved points
+        let moves_set_points = self
+            .points
+            .as_ref()
+            .is_some_and(|points| points.dimensions == dimensions && points.data == run.data);
+        let mut data = std::mem::take(&mut run.data);
+        let mut previous = Vec::with_capacity(changed.len() * dimensions);
+        for (&index, &point) in &moves {
+            let range = index * dimensions..(index + 1) * dimensions;
+            previous.extend_from_slice(&data[range.clone()]);
+            data[range].copy_from_slice(point);
+        }
+        // the previous positions follow the points, so both are measured in the same way
+        data.extend_from_slice(&previous);
+        let dbscan = &run.parameters;
+        // the index of an earlier update follows the moves instead of being built again
+        let index = run.index.take();
+        let (clusters, raw_clusters, index) =
+            match run.raw_clusters.as_ref().filter(|_| self.updatable()) {
+                Some(raw) => with_points!(&run.metric, &data, dimensions, |points| {
+                    let weighted = with_weights(points, &weights);
+                    let (weighted, previous) = weighted.split_at(point_count);
+                    let index = match index {
+                        Some(index) => {
+                            let mut index = Arc::unwrap_or_clone(index);
+                            index.move_points(weighted, &changed);
+                            index
+                        }
+                        None => SpatialIndex::new(weighted),
+                    };
+                    let raw = dbscan.update_indexed(weighted, &changed, previous, raw, &index);
+                    let points = &points[..point_count];
+                    let mut clusters = raw.clone();
+                    self.finish_clusters(points, dbscan, &mut clusters);
+                    (clusters, Some(raw), Some(Arc::new(index)))
+                }),
+                None => with_points!(&run.metric, &data, dimensions, |points| {
+                    let points = &points[..point_count];
+                    let mut clusters = self.cluster_points(dbscan, points, None);
+                    self.finish_clusters(points, dbscan, &mut clusters);
+                    (clusters, None, None)
+                }),
+            };
+        data.truncate(point_count * dimensions);
+
+        self.warnings.clear();
+        self.warnings
+            .extend(diagnostics::data_warnings(&data, dimensions));
+        if moves_set_points {
+            let points = self.points.as_mut().expect("checked above");
+            points.data.clone_from(&data);
+            points.index = match index.as_ref().filter(|_| points.metric == run.metric) {
+                Some(index) => index.clone(),
+                None => {
+                    let index = with_points!(&points.metric, &points.data, dimensions, |points| {
+                        SpatialIndex::new(points)
+                    });
+                    Arc::new(index)
+                }
+            };
+            points.warnings.clone_from(&self.warnings);
+        }
+        self.warnings.extend(diagnostics::noise_warning(&clusters));
+        let delta = analysis::changed_points(&run.clusters, &clusters, point_count);
+        self.last_run = Some(LastRun {
+            data,
+            clusters,
+            raw_clusters,
+            index,
+            ..run
+        });
+        Ok(delta.into_iter().map(|index| index as u32).collect())
+    }
+
+    /// Clusters prepared data, with the cluster limit if `limited` and with a spatial `index` of it, if any. Uses
+    /// const implementations of the Euclidean metric for up to 7 dimensions.
+    fn cluster_data(
+        &self,
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        data: &[PointScalar],
+        dimensions: usize,
+        limited: bool,
+        index: Option<&SpatialIndex>,
+    ) -> Result<(Vec<fuzzy_dbscan::Cluster>, fuzzy_dbscan::FuzzyDBSCAN), ClusterError> {
+        Ok(with_points!(&self.metric, data, dimensions, |points| {
+            let (mut clusters, parameters) = self.cluster_with(dbscan, points, limited, index);
+            self.finish_clusters(points, &parameters, &mut clusters);
+            (clusters, parameters)
+        }))
+    }
+
+    /// Clusters prepared data measured with `metric` once per parameters of `levels`, without the cluster limit.
+    fn cluster_data_levels(
+        &self,
+        metric: &Metric,
+        levels: &[fuzzy_dbscan::FuzzyDBSCAN],
+        data: &[PointScalar],
+        dimensions: usize,
+    ) -> Result<Vec<Vec<fuzzy_dbscan::Cluster>>, ClusterError> {
+        Ok(with_points!(metric, data, dimensions, |points| {
+            let mut clusters = self.cluster_points_levels(levels, points, None);
+            for (clusters, level) in clusters.iter_mut().zip(levels) {
+                self.finish_clusters(points, level, clusters);
+            }
+            clusters
+        }))
+    }
+
+    /// Applies density weighting, the core threshold, noise reassignment, the minimum mean membership and noise
+    /// compaction to clusters of `points` found with `dbscan` by `cluster_points`.
+    fn finish_clusters<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        points: &[P],
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        clusters: &mut Vec<fuzzy_dbscan::Cluster>,
+    ) {
+        let groups = (self.density_weighted_labels && self.clusters_subset())
+            .then(|| self.clustered_groups(points));
+        self.finish_weighted_clusters(points, &self.weights, dbscan, clusters, groups.as_deref());
+    }
+
+    /// Finishes clusters like `finish_clusters`, with `weights` for `points` instead of the configured ones.
+    ///
+    /// Densities count the points the clustering saw: the first point of each of `groups` (see
+    /// `clustered_groups`), which also stands for the others, or all `points` if there are no groups.
+    fn finish_weighted_clusters<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        points: &[P],
+        weights: &[f32],
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        clusters: &mut Vec<fuzzy_dbscan::Cluster>,
+        groups: Option<&[Vec<usize>]>,
+    ) {
+        if self.density_weighted_labels {
+            let mut borders: Vec<usize> = clusters
+                .iter()
+                .flatten()
+                .filter(|assignment| assignment.category == fuzzy_dbscan::Category::Border)
+                .map(|assignment| assignment.index)
+                .collect();
+            borders.sort_unstable();
+            borders.dedup();
+            let densities = match groups {
+                None => dbscan.densities(&with_weights(points, weights), &borders),
+                Some(groups) => {
+                    let positions: HashMap<usize, usize> = groups
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(position, group)| {
+                            group.iter().map(move |&index| (index, position))
+                        })
+                        .collect();
+                    let subset: Vec<P> = groups.iter().map(|group| points[group[0]]).collect();
+                    let subset_weights: Vec<f32> = if weights.is_empty() {
+                        Vec::new()
+                    } else {
+                        groups.iter().map(|group| weights[group[0]]).collect()
+                    };
+                    let border_positions: Vec<usize> =
+                        borders.iter().map(|border| positions[border]).collect();
+                    dbscan.densities(&with_weights(&subset, &subset_weights), &border_positions)
+                }
+            };
+            let densities = borders.into_iter().zip(densities).collect();
+            analysis::weight_by_density(clusters, &densities, dbscan.pts_max);
+        }
+        analysis::apply_core_threshold(clusters, self.core_threshold);
+        if let Some(factor) = self.noise_reassignment {
+            analysis::reassign_noise(points, clusters, dbscan.eps_min, dbscan.eps_max * factor);
+        }
+        if self.min_avg_membership > 0.0 {
+            *clusters = analysis::keep_confident(std::mem::take(clusters), self.min_avg_membership);
+        }
+        if self.compact_noise {
+            analysis::compact_noise(clusters);
+        }
+    }
+
+    /// Returns the parameters for clustering with each `eps_max` of `eps`, keeping the ratio of `eps_min` to
+    /// `eps_max`.
+    fn eps_levels(
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        eps: &[f64],
+    ) -> Vec<fuzzy_dbscan::FuzzyDBSCAN> {
+        let ratio = if dbscan.eps_max > 0. {
+            dbscan.eps_min / dbscan.eps_max
+        } else {
+            1.
+        };
+        eps.iter()
+            .map(|&eps| fuzzy_dbscan::FuzzyDBSCAN {
+                // an infinite eps_max with an eps_min of 0 keeps it at 0 instead of NaN
+                eps_min: if ratio == 0. { 0. } else { eps * ratio },
+                eps_max: eps,
+                ..dbscan.clone()
+            })
+            .collect()
+    }
+
+    fn cluster_with<P: fuzzy_dbscan::MetricSpace + Copy>(
+        &self,
+        dbscan: &fuzzy_dbscan::FuzzyDBSCAN,
+        points: &[P],
+        limited: bool,
+        index: Option<&SpatialIndex>,
+    ) -> (Vec<fuzzy_dbscan::Cluster>, fuzzy_dbscan::FuzzyDBSCAN) {
+        if limited {
+            self.cluster_limited(dbscan, points, index)
+        } else {
+            (self.cluster_points(dbscan, points, index), dbscan.clone())
+        }
+    }
+
+    /// Clusters data once per eps of `schedule` (in increasing order) and links each cluster to the cluster of the
+    /// next level that most of its members belong to.
+    fn cluster_hierarchy(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        mut schedule: Vec<f64>,
+    ) -> Result<ClusterHierarchy, ClusterError> {
+        self.warnings.clear();
+        let (data, dimensions, _) = self.prepare(packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&self.clustered_indices(data.len() / dimensions))?;
+        self.check_point_options(data.len() / dimensions)?;
+        schedule.sort_by(f64::total_cmp);
+        let levels = self.cluster_data_levels(
+            &self.metric,
+            &Self::eps_levels(&dbscan, &schedule),
+            &data,
+            dimensions,
+        )?;
+
+        let real = |clusters: &[fuzzy_dbscan::Cluster]| -> Vec<fuzzy_dbscan::Cluster> {
+            clusters
+                .iter()
+                .filter(|cluster| !analysis::is_noise(cluster))
+                .cloned()
+                .collect()
+        };
+        let mut hierarchy = ClusterHierarchy {
+            eps: schedule,
+            level_offsets: vec![0],
+            parents: Vec::new(),
+            sizes: Vec::new(),
+            packed: levels
+                .iter()
+                .map(|clusters| pack_clusters(clusters))
+                .collect(),
+        };
+        for (level, clusters) in levels.iter().enumerate() {
+            let clusters = real(clusters);
+            let next_offset = hierarchy.level_offsets[level] + clusters.len() as u32;
+            let parents = match levels.get(level + 1) {
+                Some(next) => analysis::parent_clusters(&clusters, &real(next)),
+                None => vec![None; clusters.len()],
+            };
+            hierarchy.parents.extend(
+                parents.into_iter().map(|parent| {
+                    parent.map_or(-1, |parent| (next_offset as usize + parent) as i32)
+                }),
+            );
+            hierarchy
+                .sizes
+                .extend(clusters.iter().map(|cluster| cluster.len() as u32));
+            hierarchy.level_offsets.push(next_offset);
+        }
+        Ok(hierarchy)
+    }
+
+    /// Clusters data once per eps of `eps_levels`, in the given order, sharing the neighborhood search between
+    /// levels.
+    fn cluster_multilevel(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        eps_levels: Vec<f64>,
+    ) -> Result<ClusterLevels, ClusterError> {
+        self.warnings.clear();
+        let (data, dimensions, _) = self.prepare(packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&self.clustered_indices(data.len() / dimensions))?;
+        self.check_point_options(data.len() / dimensions)?;
+        let levels = self.cluster_data_levels(
+            &self.metric,
+            &Self::eps_levels(&dbscan, &eps_levels),
+            &data,
+            dimensions,
+        )?;
+        Ok(ClusterLevels {
+            eps: eps_levels,
+            packed: levels
+                .iter()
+                .map(|clusters| pack_clusters(clusters))
+                .collect(),
+        })
+    }
+
+    /// Clusters data with each ensemble metric (or only the configured metric if there are none) and combines the
+    /// clusterings into consensus clusters.
+    fn cluster_ensemble(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<EnsembleClusters, ClusterError> {
+        self.warnings.clear();
+        let (data, dimensions, _) = self.prepare(packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&self.clustered_indices(data.len() / dimensions))?;
+        let point_count = data.len() / dimensions;
+        self.check_point_options(point_count)?;
+        let metrics = if self.ensemble_metrics.is_empty() {
+            std::slice::from_ref(&self.metric)
+        } else {
+            &self.ensemble_metrics
+        };
+        let mut runs = Vec::with_capacity(metrics.len());
+        for metric in metrics {
+            let mut levels =
+                self.cluster_data_levels(metric, std::slice::from_ref(&dbscan), &data, dimensions)?;
+            runs.push(levels.pop().expect("one level"));
+        }
+        let (mut clusters, agreement) = analysis::consensus(&runs, point_count);
+        if self.compact_noise {
+            analysis::compact_noise(&mut clusters);
+        }
+        Ok(EnsembleClusters {
+            packed: pack_clusters(&clusters),
+            agreement: agreement.into_iter().map(|score| score as f32).collect(),
+        })
+    }
+
+    /// Fails if clustering `point_count` points would likely exceed the memory budget.
+    fn check_memory(&self, point_count: usize, dimensions: usize) -> Result<(), ClusterError> {
+        let Some(budget) = self.memory_budget else {
+            return Ok(());
+        };
+        let dimensions = self
+            .projection
+            .map_or(dimensions, |projection| projection.target_dimensions);
+        let points = point_count
+            * (dimensions * std::mem::size_of::<PointScalar>()
+                + std::mem::size_of::<Weighted<MetricPoint>>());
+        // the duplicate check of the data warnings is done before clustering starts
+        let clustering = SpatialIndex::estimated_memory(point_count, dimensions)
+            + fuzzy_dbscan::ClusterState::estimated_memory(point_count);
+        let required = points + diagnostics::estimated_memory(point_count).max(clustering);
+        if required > budget {
+            return Err(ClusterError::InsufficientMemory { required, budget });
+        }
+        Ok(())
+    }
+
+    /// Creates the state of a clustering of `point_count` points with the seeds, excluded core points and
+    /// constraints, where `position` maps an input point to its position among the clustered points, if any.
+    ///
+    /// Options for points that are not clustered are dropped. A representative of near-duplicates is excluded if
+    /// any of them is, and constraints between near-duplicates of the same representative are dropped.
+    fn initial_state(
+        &self,
+        point_count: usize,
+        position: impl Fn(usize) -> Option<usize>,
+    ) -> fuzzy_dbscan::ClusterState {
+        let seeds: Vec<(usize, u32)> = self
+            .seeds
+            .iter()
+            .filter_map(|&(index, hint)| Some((position(index)?, hint)))
+            .collect();
+        let excluded: Vec<usize> = self
+            .excluded_cores
+            .iter()
+            .filter_map(|&index| position(index))
+            .collect();
+        let pairs = |pairs: &[(usize, usize)]| -> Vec<(usize, usize)> {
+            pairs
+                .iter()
+                .filter_map(|&(a, b)| Some((position(a)?, position(b)?)))
+                .filter(|(a, b)| a != b)
+                .collect()
+        };
+        let mut state = fuzzy_dbscan::ClusterState::with_seeds(point_count, &seeds);
+        state.exclude_cores(&excluded);
+        state.constrain(&pairs(&self.must_links), &pairs(&self.cannot_links));
+        state
+    }
+
+    /// Checks that seeds, excluded core points, constraints, timestamps, ids and weights fit `point_count` points.
+    fn check_point_options(&self, point_count: usize) -> Result<(), ClusterError> {
+        if let Some(&(index, _)) = self.seeds.iter().find(|&&(index, _)| index >= point_count) {
+            return Err(ClusterError::IndexOutOfRange { index, point_count });
+        }
+        if let Some(index) = self
+            .must_links
+            .iter()
+            .chain(&self.cannot_links)
+            .flat_map(|&(a, b)| [a, b])
+            .find(|&index| index >= point_count)
+        {
+            return Err(ClusterError::IndexOutOfRange { index, point_count });
+        }
+        if let Some(&index) = self
+            .excluded_cores
+            .iter()
+            .find(|&&index| index >= point_count)
+        {
+            return Err(ClusterError::IndexOutOfRange { index, point_count });
+        }
+        if self.time_window.is_some() && self.timestamps.len() != point_count {
+            return Err(ClusterError::PointVectorLengthMismatch {
+                param_name: "timestamps",
+                expected: point_count,
+                got: self.timestamps.len(),
+            });
+        }
+        if let Some(ids) = &self.point_ids {
+            if ids.len() != point_count {
+                return Err(ClusterError::PointVectorLengthMismatch {
+                    param_name: "ids",
+                    expected: point_count,
+                    got: ids.len(),
+                });
+            }
+        }
+        if !self.weights.is_empty() && self.weights.len() != point_count {
+            return Err(ClusterError::PointVectorLengthMismatch {
+                param_name: "weights",
+                expected: point_count,
+                got: self.weights.len(),
+            });
+        }
+        check_weights(&self.weights)
+    }
+
+    /// Reads packed (or interleaved) data, records warnings about it and applies the scaling and random
+    /// projection, if any.
+    ///
+    /// Returns the points to cluster, their number of dimensions and the scaling fitted to them.
+    fn prepare(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<(Vec<PointScalar>, usize, Option<FittedScaling>), ClusterError> {
+        if self.categorical_last_dimension && self.projection.is_some() {
+            return Err(ClusterError::ProjectedCategories);
+        }
+        let (data, weights) = self.read_weighted(packed_data, dimensions)?;
+        if self.embedded_weights {
+            self.weights = weights;
+        }
+        self.check_memory(data.len() / dimensions, dimensions)?;
+        self.metric.validate_data(&data, dimensions)?;
+        self.warnings
+            .extend(diagnostics::dimension_warning(data.len(), dimensions));
+        self.warnings
+            .extend(diagnostics::data_warnings(&data, dimensions));
+        let scaling = self.scaling.map(|scaling| scaling.fit(&data, dimensions));
+        let (data, dimensions) = self.transform(data, dimensions, scaling.as_ref())?;
+        Ok((data, dimensions, scaling))
+    }
+
+    /// Reads packed or interleaved data into packed points with `dimensions` scalars each, without their weight
+    /// column if weights are embedded.
+    fn read_input<'a>(
+        &self,
+        packed_data: &'a [u8],
+        dimensions: usize,
+    ) -> Result<Cow<'a, [PointScalar]>, ClusterError> {
+        Ok(self.read_weighted(packed_data, dimensions)?.0)
+    }
+
+    /// Reads packed or interleaved data like `read_input`, and also returns the weight column if weights are
+    /// embedded (or no weights otherwise).
+    fn read_weighted<'a>(
+        &self,
+        packed_data: &'a [u8],
+        dimensions: usize,
+    ) -> Result<(Cow<'a, [PointScalar]>, Vec<f32>), ClusterError> {
+        if dimensions == 0 {
+            return Err(ClusterError::UnexpectedEndOfInput);
+        }
+        let data = self.read_scalars(packed_data, dimensions + self.embedded_weights as usize)?;
+        if !self.embedded_weights {
+            return Ok((data, Vec::new()));
+        }
+        let points = data.chunks_exact(dimensions + 1);
+        let weights = points.clone().map(|point| point[dimensions]).collect();
+        let data = points
+            .flat_map(|point| &point[..dimensions])
+            .copied()
+            .collect();
+        Ok((Cow::Owned(data), weights))
+    }
+
+    /// Reads packed or interleaved data into packed points with `dimensions` scalars each, including embedded
+    /// weights.
+    fn read_scalars<'a>(
+        &self,
+        packed_data: &'a [u8],
+        dimensions: usize,
+    ) -> Result<Cow<'a, [PointScalar]>, ClusterError> {
+        let data = if self.half_precision {
+            Cow::Owned(half_byte_array_as_scalars(packed_data)?)
+        } else {
+            Cow::Borrowed(byte_array_as_scalar_type(packed_data)?)
+        };
+        let data = match self.interleaving {
+            Some((stride, offset)) => {
+                Cow::Owned(read_interleaved(&data, dimensions, stride, offset)?)
+            }
+            None => data,
+        };
+        if dimensions == 0 || !data.len().is_multiple_of(dimensions) {
+            return Err(ClusterError::UnexpectedEndOfInput);
+        }
+        Ok(data)
+    }
+
+    /// Applies the scaling, fitted to `data`, and the random projection, if any, and returns the projected points
+    /// and their number of dimensions.
+    fn project(&self, data: Cow<[PointScalar]>, dimensions: usize) -> (Vec<PointScalar>, usize) {
+        let scaling = self.scaling.map(|scaling| scaling.fit(&data, dimensions));
+        self.transform(data, dimensions, scaling.as_ref())
+            .expect("scaling is fitted to the data")
+    }
+
+    /// Applies `scaling` (e.g. that of the most recent clustering, for queries) and the random projection, if any,
+    /// and returns the projected points and their number of dimensions.
+    fn transform(
+        &self,
+        data: Cow<[PointScalar]>,
+        dimensions: usize,
+        scaling: Option<&FittedScaling>,
+    ) -> Result<(Vec<PointScalar>, usize), ClusterError> {
+        let data = match scaling {
+            Some(scaling) if scaling.centers.len() != dimensions => {
+                return Err(ClusterError::DimensionMismatch {
+                    expected: scaling.centers.len(),
+                    got: dimensions,
+                });
+            }
+            Some(scaling) => {
+                let mut data = data.into_owned();
+                scaling.apply(&mut data);
+                Cow::Owned(data)
+            }
+            None => data,
+        };
+        Ok(match self.projection {
+            Some(projection) => (
+                projection.apply(&data, dimensions),
+                projection.target_dimensions,
+            ),
+            None => (data.into_owned(), dimensions),
+        })
+    }
+
+    /// Assigns query points to the cluster of the nearest core point of the most recent clustering within
+    /// `eps_max`, or -1 for noise.
+    fn assign_queries(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<i32>, ClusterError> {
+        let run = self.last_run()?;
+        let (queries, dimensions) = self.transform(
+            self.read_input(packed_data, dimensions)?,
+            dimensions,
+            run.scaling.as_ref(),
+        )?;
+        if dimensions != run.dimensions {
+            return Err(ClusterError::DimensionMismatch {
+                expected: run.dimensions,
+                got: dimensions,
+            });
+        }
+        let points = run.points();
+        let clusters: Vec<&fuzzy_dbscan::Cluster> = run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .collect();
+        Ok(metric_points(&queries, dimensions, &run.metric)
+            .iter()
+            .map(|query| {
+                analysis::nearest_core_cluster(&points, &clusters, query, run.parameters.eps_max)
+                    .map_or(-1, |cluster| cluster as i32)
+            })
+            .collect())
+    }
+
+    /// Determines which points are core points, without sampling or clustering.
+    fn core_mask(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<u8>, ClusterError> {
+        self.warnings.clear();
+        let (data, dimensions, _) = self.prepare(packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&(0..data.len() / dimensions).collect::<Vec<_>>())?;
+        self.metric.validate(dimensions)?;
+        self.check_point_options(data.len() / dimensions)?;
+        let points = self.weighted(&metric_points(&data, dimensions, &self.metric));
+        let mut labels = dbscan.core_labels(&points);
+        for &index in &self.excluded_cores {
+            labels[index] = 0.0;
+        }
+        Ok(labels
+            .into_iter()
+            .map(|label| u8::from(label > 0.0 && label >= self.core_threshold))
+            .collect())
+    }
+
+    /// Returns the distance between the points at `indices_a[k]` and `indices_b[k]` for every `k`, measured like
+    /// a clustering of `packed_data` would measure it.
+    fn pairwise_distances(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+        indices_a: &[u32],
+        indices_b: &[u32],
+    ) -> Result<Vec<f32>, ClusterError> {
+        if indices_a.len() != indices_b.len() {
+            return Err(ClusterError::LengthMismatch {
+                a: indices_a.len(),
+                b: indices_b.len(),
+            });
+        }
+        let (data, dimensions) =
+            self.project(self.read_input(packed_data, dimensions)?, dimensions);
+        self.metric.validate(dimensions)?;
+        let points = metric_points(&data, dimensions, &self.metric);
+        let point = |index: u32| {
+            points
+                .get(index as usize)
+                .ok_or(ClusterError::IndexOutOfRange {
+                    index: index as usize,
+                    point_count: points.len(),
+                })
+        };
+        indices_a
+            .iter()
+            .zip(indices_b)
+            .map(|(&a, &b)| Ok(point(a)?.distance(point(b)?) as f32))
+            .collect()
+    }
+
+    /// Groups points of `packed_data` that are within `tolerance` of an earlier point, keeping only groups with more
+    /// than one point.
+    fn find_duplicates(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+        tolerance: f64,
+    ) -> Result<IndexGroups, ClusterError> {
+        let (data, dimensions) =
+            self.project(self.read_input(packed_data, dimensions)?, dimensions);
+        self.metric.validate(dimensions)?;
+        let points = metric_points(&data, dimensions, &self.metric);
+        let indices: Vec<usize> = (0..points.len()).collect();
+        let mut groups = IndexGroups {
+            offsets: vec![0],
+            indices: Vec::new(),
+        };
+        for group in collapse_near_duplicates(&points, &indices, tolerance) {
+            if group.len() > 1 {
+                groups
+                    .indices
+                    .extend(group.into_iter().map(|index| index as u32));
+                groups.offsets.push(groups.indices.len() as u32);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Returns the minimum spanning tree over the points of `packed_data`, measured with the configured metric.
+    fn minimum_spanning_tree(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<SpanningTree, ClusterError> {
+        let (data, dimensions) =
+            self.project(self.read_input(packed_data, dimensions)?, dimensions);
+        self.metric.validate(dimensions)?;
+        let edges = graph::minimum_spanning_tree(&metric_points(&data, dimensions, &self.metric));
+        Ok(SpanningTree {
+            endpoints: edges
+                .iter()
+                .flat_map(|edge| [edge.a as u32, edge.b as u32])
+                .collect(),
+            weights: edges.iter().map(|edge| edge.weight as f32).collect(),
+        })
+    }
+
+    /// Prepares points for `recluster` and `neighbors_of` and builds their spatial index.
+    fn set_points(&mut self, packed_data: &[u8], dimensions: usize) -> Result<(), ClusterError> {
+        self.warnings.clear();
+        self.points = None;
+        let (data, dimensions, scaling) = self.prepare(packed_data, dimensions)?;
+        self.metric.validate(dimensions)?;
+        let index = with_points!(&self.metric, &data, dimensions, |points| {
+            SpatialIndex::new(points)
+        });
+        self.points = Some(IndexedPoints {
+            data,
+            dimensions,
+            metric: self.metric.clone(),
+            scaling,
+            warnings: self.warnings.clone(),
+            index: Arc::new(index),
+        });
+        Ok(())
+    }
+
+    /// Clusters the points of `set_points` like `run`, reusing their spatial index, and keeps the result as the
+    /// most recent clustering.
+    fn recluster(&mut self) -> Result<&LastRun, ClusterError> {
+        self.last_run = None;
+        let mut warnings = Vec::new();
+        let run = self.recluster_with(self.inner.clone(), &mut warnings);
+        self.warnings = warnings;
+        Ok(self.last_run.insert(run?))
+    }
+
+    /// Clusters the points of `set_points` with `dbscan` like `recluster`, but returns the result instead of
+    /// keeping it and adds warnings to `warnings`.
+    fn recluster_with(
+        &self,
+        dbscan: fuzzy_dbscan::FuzzyDBSCAN,
+        warnings: &mut Vec<String>,
+    ) -> Result<LastRun, ClusterError> {
+        let points = self.points.as_ref().ok_or(ClusterError::NoPoints)?;
+        let point_count = points.data.len() / points.dimensions;
+        warnings.extend(points.warnings.iter().cloned());
+        let dbscan = self.resolve_with(dbscan, &self.clustered_indices(point_count), warnings)?;
+        self.check_point_options(point_count)?;
+        self.metric.validate(points.dimensions)?;
+        // the index is only valid for the metric it was built with
+        let index = (points.metric == self.metric).then(|| points.index.clone());
+        let (clusters, parameters) = self.cluster_data(
+            &dbscan,
+            &points.data,
+            points.dimensions,
+            true,
+            index.as_deref(),
+        )?;
+        let run = LastRun {
+            data: points.data.clone(),
+            dimensions: points.dimensions,
+            metric: self.metric.clone(),
+            parameters,
+            clusters,
+            raw_clusters: None,
+            scaling: points.scaling.clone(),
+            index,
+        };
+        warnings.extend(diagnostics::noise_warning(&run.clusters));
+        Ok(run)
+    }
+
+    /// Returns the points of `set_points` within `eps` of the point at `index`, excluding itself, sorted by index.
+    fn neighbors_of(&self, index: usize, eps: f64) -> Result<Vec<u32>, ClusterError> {
+        let points = self.points.as_ref().ok_or(ClusterError::NoPoints)?;
+        let point_count = points.data.len() / points.dimensions;
+        if index >= point_count {
+            return Err(ClusterError::IndexOutOfRange { index, point_count });
+        }
+        let neighbors = with_points!(&points.metric, &points.data, points.dimensions, |data| {
+            points.index.neighbors(data, index, eps)
+        });
+        Ok(neighbors.into_iter().map(|index| index as u32).collect())
+    }
+
+    /// Starts a clustering that is advanced with `continue_clustering`.
+    fn start_clustering(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<(), ClusterError> {
+        self.warnings.clear();
+        self.session = None;
+        let (data, dimensions, scaling) = self.prepare(&packed_data, dimensions)?;
+        let dbscan = self.resolve_parameters(&(0..data.len() / dimensions).collect::<Vec<_>>())?;
+        self.metric.validate(dimensions)?;
+        let point_count = data.len() / dimensions;
+        self.check_point_options(point_count)?;
+        self.session = Some(Session {
+            data,
+            dimensions,
+            metric: self.metric.clone(),
+            dbscan,
+            state: self.initial_state(point_count, Some),
+            scaling,
+        });
+        Ok(())
+    }
+
+    /// Advances the current session; once it is done, its result becomes the most recent clustering.
+    fn continue_clustering(&mut self, max_points: usize) -> Result<bool, ClusterError> {
+        let mut session = self.session.take().ok_or(ClusterError::NoSession)?;
+        let points = self.weighted(&metric_points(
+            &session.data,
+            session.dimensions,
+            &session.metric,
+        ));
+        if !session.dbscan.step(&points, &mut session.state, max_points) {
+            self.session = Some(session);
+            return Ok(false);
+        }
+        let mut clusters = session.state.into_clusters();
+        let points = metric_points(&session.data, session.dimensions, &session.metric);
+        // a stepped clustering clusters all points
+        self.finish_weighted_clusters(&points, &self.weights, &session.dbscan, &mut clusters, None);
+        self.warnings.extend(diagnostics::noise_warning(&clusters));
+        self.last_run = Some(LastRun {
+            data: session.data,
+            dimensions: session.dimensions,
+            metric: session.metric,
+            parameters: session.dbscan,
+            clusters,
+            raw_clusters: None,
+            scaling: session.scaling,
+            index: None,
+        });
+        Ok(true)
+    }
+
+    /// Advances the current session like `continue_clustering` and reports its progress.
+    fn step(&mut self, max_points: usize) -> Result<StepResult, ClusterError> {
+        let done = self.continue_clustering(max_points)?;
+        let progress = match &self.session {
+            Some(session) if !done => session.state.progress(),
+            _ => 1.0,
+        };
+        Ok(StepResult { done, progress })
+    }
+
+    fn serialize_state(&self) -> Result<Vec<u8>, ClusterError> {
+        Ok(self
+            .session
+            .as_ref()
+            .ok_or(ClusterError::NoSession)?
+            .to_bytes())
+    }
+
+    fn resume_from_state(&mut self, state: &[u8]) -> Result<(), ClusterError> {
+        self.warnings.clear();
+        self.session = None;
+        let (data, dimensions, state) = Session::read_bytes(state)?;
+        let dbscan = self.resolve_parameters(&(0..data.len() / dimensions).collect::<Vec<_>>())?;
+        self.metric.validate(dimensions)?;
+        self.session = Some(Session {
+            data,
+            dimensions,
+            metric: self.metric.clone(),
+            dbscan,
+            state,
+            scaling: None,
+        });
+        Ok(())
+    }
+
+    fn last_run(&self) -> Result<&LastRun, ClusterError> {
+        self.last_run.as_ref().ok_or(ClusterError::NoClustering)
+    }
+
+    /// Returns the spatial index of the points of `run`, if they have one and are still measured with the metric
+    /// that it was built for.
+    fn run_index<'a>(&self, run: &'a LastRun) -> Option<&'a SpatialIndex> {
+        run.index.as_deref().filter(|_| run.metric == self.metric)
+    }
+
+    fn memberships(&self, normalize: bool) -> Result<Vec<f32>, ClusterError> {
+        let run = self.last_run()?;
+        Ok(analysis::membership_matrix(&run.clusters, run.point_count(), normalize).0)
+    }
+
+    /// Returns the packed centroid of each cluster, excluding the noise group.
+    fn centroids(&self) -> Result<Vec<PointScalar>, ClusterError> {
+        let run = self.last_run()?;
+        Ok(run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .flat_map(|cluster| analysis::centroid(&run.data, run.dimensions, cluster))
+            .collect())
+    }
+
+    /// Returns how much each dimension separates the clusters of the most recent clustering.
+    fn dimension_separation(&self) -> Result<Vec<f32>, ClusterError> {
+        let run = self.last_run()?;
+        let clusters: Vec<&fuzzy_dbscan::Cluster> = run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .collect();
+        Ok(
+            analysis::dimension_separation(&run.data, run.dimensions, &clusters)
+                .into_iter()
+                .map(|share| share as f32)
+                .collect(),
+        )
+    }
+
+    /// Returns the row-major matrix of distances between the centroids of all clusters, excluding the noise group.
+    fn centroid_distances(&self) -> Result<Vec<f32>, ClusterError> {
+        let run = self.last_run()?;
+        let centroids = self.centroids()?;
+        let centroids: Vec<&[PointScalar]> = centroids.chunks_exact(run.dimensions).collect();
+        Ok(centroids
+            .iter()
+            .flat_map(|a| centroids.iter().map(|b| run.metric.distance(a, b) as f32))
+            .collect())
+    }
+
+    /// Returns an id for each cluster, excluding the noise group, that is reused from the closest previous cluster.
+    fn cluster_ids(&self) -> Result<Vec<u32>, ClusterError> {
+        let run = self.last_run()?;
+        let centroids = self.centroids()?;
+        let cluster_count = centroids.len() / run.dimensions;
+        let Some(previous) = &self.previous else {
+            return Ok((0..cluster_count as u32).collect());
+        };
+        // `set_previous_clusters` checked that there is a whole number of coordinates per id
+        if previous.centroids.len() != previous.ids.len() * run.dimensions {
+            return Err(ClusterError::DimensionMismatch {
+                expected: run.dimensions,
+                got: previous.centroids.len() / previous.ids.len(),
+            });
+        }
+        let centroids = metric_points(&centroids, run.dimensions, &run.metric);
+        let previous_centroids = metric_points(&previous.centroids, run.dimensions, &run.metric);
+        let matches = if self.optimal_matching {
+            analysis::match_clusters_optimal(&centroids, &previous_centroids, previous.max_distance)
+        } else {
+            analysis::match_clusters(&centroids, &previous_centroids, previous.max_distance)
+        };
+        let mut fresh = previous.ids.iter().max().map_or(0, |&id| id + 1);
+        Ok(matches
+            .into_iter()
+            .map(|matched| match matched {
+                Some(j) => previous.ids[j],
+                None => {
+                    fresh += 1;
+                    fresh - 1
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the id (see `cluster_ids`) of each point's cluster with the highest membership, and that membership.
+    fn flat_labels(&self) -> Result<FlatLabels, ClusterError> {
+        let run = self.last_run()?;
+        let cluster_ids = self.cluster_ids()?;
+        let (matrix, cluster_count) =
+            analysis::membership_matrix(&run.clusters, run.point_count(), false);
+        let mut labels = FlatLabels {
+            ids: vec![-1; run.point_count()],
+            memberships: vec![0.0; run.point_count()],
+        };
+        if cluster_count == 0 {
+            return Ok(labels);
+        }
+        let mut rng = self.tie_break_seed.map(|seed| rng::Rng::new(seed as u64));
+        for (point, row) in matrix.chunks_exact(cluster_count).enumerate() {
+            let mut ties = 0;
+            for (cluster, &membership) in row.iter().enumerate() {
+                if membership == 0.0 || membership < labels.memberships[point] {
+                    continue;
+                }
+                if membership > labels.memberships[point] {
+                    ties = 0;
+                }
+                ties += 1;
+                // reservoir sampling keeps each of the tied clusters with the same probability
+                let keep = match &mut rng {
+                    Some(rng) => rng.below(ties) == 0,
+                    None => ties == 1,
+                };
+                if keep {
+                    labels.ids[point] = cluster_ids[cluster] as i32;
+                    labels.memberships[point] = membership;
+                }
+            }
+        }
+        Ok(labels)
+    }
+
+    /// Clusters data and returns, for each input point, its cluster id and membership as in `flat_labels` and its
+    /// category in that cluster.
+    fn cluster_point_records(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<(i32, fuzzy_dbscan::Category, f32)>, ClusterError> {
+        self.run(packed_data, dimensions)?;
+        let labels = self.flat_labels()?;
+        let cluster_ids = self.cluster_ids()?;
+        let run = self.last_run()?;
+        let mut categories = vec![fuzzy_dbscan::Category::Noise; run.point_count()];
+        let real = run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster));
+        for (cluster, &id) in real.zip(&cluster_ids) {
+            for assignment in cluster {
+                if labels.ids[assignment.index] == id as i32 {
+                    categories[assignment.index] = assignment.category;
+                }
+            }
+        }
+        Ok(labels
+            .ids
+            .into_iter()
+            .zip(categories)
+            .zip(labels.memberships)
+            .map(|((id, category), membership)| (id, category, membership))
+            .collect())
+    }
+
+    /// Returns the most common attribute value of each cluster's members and its share, excluding the noise group.
+    fn dominant_attributes(&self, attributes: &[u32]) -> Result<DominantAttributes, ClusterError> {
+        let run = self.last_run()?;
+        if attributes.len() != run.point_count() {
+            return Err(ClusterError::PointVectorLengthMismatch {
+                param_name: "attributes",
+                expected: run.point_count(),
+                got: attributes.len(),
+            });
+        }
+        let (values, purities) = run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .map(|cluster| {
+                let (value, purity) = analysis::dominant_value(attributes, cluster);
+                (value, purity as f32)
+            })
+            .unzip();
+        Ok(DominantAttributes { values, purities })
+    }
+
+    /// Returns the members per bounding box volume of each cluster, excluding the noise group.
+    fn cluster_densities(&self) -> Result<Vec<f32>, ClusterError> {
+        let run = self.last_run()?;
+        Ok(run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .map(|cluster| analysis::box_density(&run.data, run.dimensions, cluster) as f32)
+            .collect())
+    }
+
+    /// Returns packed RGB colors for each cluster, excluding the noise group.
+    fn cluster_colors(&self) -> Result<Vec<u8>, ClusterError> {
+        let sizes: Vec<usize> = self
+            .last_run()?
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .map(|cluster| cluster.len())
+            .collect();
+        Ok(analysis::spread_colors(&sizes).concat())
+    }
+
+    /// Clusters data and packs the result with a header, see [`pack_clusters_with_header`].
+    fn cluster_with_header(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, ClusterError> {
+        let label_bits = self.label_bits;
+        let run = self.run(&packed_data, dimensions)?;
+        pack_clusters_with_header(&run.clusters, dimensions, run.point_count(), label_bits)
+    }
+
+    /// Clusters data and packs the real clusters with a header, see [`pack_clusters_without_noise`].
+    fn cluster_without_noise(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, ClusterError> {
+        let label_bits = self.label_bits;
+        let run = self.run(&packed_data, dimensions)?;
+        pack_clusters_without_noise(&run.clusters, dimensions, run.point_count(), label_bits)
+    }
+
+    /// Clusters data and encodes the result as MessagePack, see [`ClusterRecords`](crate::messagepack::ClusterRecords).
+    #[cfg(feature = "messagepack")]
+    fn cluster_messagepack(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, ClusterError> {
+        let run = self.run(&packed_data, dimensions)?;
+        Ok(
+            crate::messagepack::ClusterRecords::new(&run.clusters, dimensions, run.point_count())
+                .to_bytes(),
+        )
+    }
+
+    /// Clusters data and returns the result in compressed sparse row layout.
+    fn cluster_csr(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<CsrClusters, ClusterError> {
+        self.run(&packed_data, dimensions)?;
+        let clusters = &self.last_run()?.clusters;
+        let mut offsets = Vec::with_capacity(clusters.len() + 1);
+        offsets.push(0);
+        for cluster in clusters {
+            offsets.push(offsets[offsets.len() - 1] + cluster.len() as u32);
+        }
+        let ids = self.point_ids.as_ref().map(|ids| {
+            clusters
+                .iter()
+                .flatten()
+                .map(|assignment| ids[assignment.index])
+                .collect()
+        });
+        Ok(CsrClusters {
+            offsets,
+            indices: clusters.iter().flatten().map(|a| a.index as u32).collect(),
+            ids,
+            categories: clusters
+                .iter()
+                .flatten()
+                .map(|a| category_code(a.category))
+                .collect(),
+            labels: clusters.iter().flatten().map(|a| a.label as f32).collect(),
+            has_noise: clusters.last().is_some_and(analysis::is_noise),
+        })
+    }
+
+    /// Returns the original index of each cluster's medoid, excluding the noise group.
+    fn medoids(&self) -> Result<Vec<u32>, ClusterError> {
+        let run = self.last_run()?;
+        let points = run.points();
+        Ok(run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .map(|cluster| analysis::medoid(&points, cluster) as u32)
+            .collect())
+    }
+
+    /// Clusters the noise group of the most recent clustering again with classic DBSCAN parameters and adds the
+    /// resulting clusters to it. Points that are still noise form the new noise group.
+    fn recluster_noise(&mut self, eps: f64, pts: f64) -> Result<Vec<u8>, ClusterError> {
+        let noise: Vec<usize> = match self.last_run()?.clusters.last() {
+            Some(cluster) if analysis::is_noise(cluster) => {
+                cluster.iter().map(|assignment| assignment.index).collect()
+            }
+            _ => Vec::new(),
+        };
+        let dbscan = self.resolve(
+            fuzzy_dbscan::FuzzyDBSCAN {
+                eps_min: eps,
+                eps_max: eps,
+                pts_min: pts,
+                pts_max: pts,
+            },
+            &noise,
+        )?;
+        let run = self.last_run.as_ref().expect("last run exists");
+        if !run.clusters.last().is_some_and(analysis::is_noise) {
+            return Ok(pack_clusters(&run.clusters));
+        }
+        let noise = run.clusters.last().expect("noise group exists");
+        let points = run.points();
+        let subset: Vec<MetricPoint> = noise
+            .iter()
+            .map(|assignment| points[assignment.index])
+            .collect();
+        let weights: Vec<f32> = if self.weights.is_empty() {
+            Vec::new()
+        } else {
+            noise
+                .iter()
+                .map(|assignment| self.weights[assignment.index])
+                .collect()
+        };
+        let positions: HashMap<usize, usize> = noise
+            .iter()
+            .enumerate()
+            .map(|(position, assignment)| (assignment.index, position))
+            .collect();
+        let state = self.initial_state(subset.len(), |index| positions.get(&index).copied());
+        let mut clusters = fuzzy_dbscan::FuzzyDBSCAN::cluster_levels(
+            std::slice::from_ref(&dbscan),
+            &with_weights(&subset, &weights),
+            &state,
+        )
+        .pop()
+        .unwrap();
+        self.finish_weighted_clusters(&subset, &weights, &dbscan, &mut clusters, None);
+        let clusters: Vec<fuzzy_dbscan::Cluster> = clusters
+            .into_iter()
+            .map(|cluster| {
+                cluster
+                    .into_iter()
+                    .map(|assignment| fuzzy_dbscan::Assignment {
+                        index: noise[assignment.index].index,
+                        core_index: assignment.core_index.map(|core| noise[core].index),
+                        ..assignment
+                    })
+                    .collect()
+            })
+            .collect();
+        let run = self.last_run.as_mut().expect("last run exists");
+        run.clusters.pop();
+        run.clusters.extend(clusters);
+        // an incremental update would regrow the clusters of the first pass only
+        run.raw_clusters = None;
+        if self.compact_noise {
+            analysis::compact_noise(&mut run.clusters);
+        }
+        Ok(pack_clusters(&run.clusters))
+    }
+
+    /// Clusters the points of the most recent clustering again with `eps` and the given densities and returns the
+    /// number of clusters, excluding the noise group.
+    fn count_clusters(
+        &mut self,
+        eps: f64,
+        pts_min: f64,
+        pts_max: f64,
+    ) -> Result<usize, ClusterError> {
+        let point_count = self.last_run()?.point_count();
+        let parameters = fuzzy_dbscan::FuzzyDBSCAN {
+            pts_min,
+            pts_max,
+            ..self.inner.clone()
+        };
+        let level = Self::eps_levels(&parameters, &[eps]).remove(0);
+        let dbscan = self.resolve(level, &self.clustered_indices(point_count))?;
+        self.check_point_options(point_count)?;
+        let run = self.last_run()?;
+        let (clusters, _) = self.cluster_data(
+            &dbscan,
+            &run.data,
+            run.dimensions,
+            false,
+            self.run_index(run),
+        )?;
+        Ok(clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+            .count())
+    }
+
+    /// Clusters the points of the most recent clustering `runs` times with seeded Gaussian jitter of standard
+    /// deviation `magnitude` and returns, per point, the fraction of runs in which its clusters stayed the same
+    /// (see [`analysis::changed_points`]).
+    fn assignment_stability(
+        &self,
+        magnitude: f64,
+        runs: usize,
+        seed: u32,
+    ) -> Result<Vec<f32>, ClusterError> {
+        let run = self.last_run()?;
+        let point_count = run.point_count();
+        self.check_point_options(point_count)?;
+        if runs == 0 {
+            return Ok(vec![1.0; point_count]);
+        }
+        // categories are compared for equality, so jitter would separate every point from its category
+        let jittered_dimensions = match run.metric {
+            Metric::Categorical { .. } => run.dimensions - 1,
+            _ => run.dimensions,
+        };
+        let mut rng = rng::Rng::new(seed as u64);
+        let mut kept = vec![0usize; point_count];
+        let mut data = run.data.clone();
+        for _ in 0..runs {
+            for (point, original) in data
+                .chunks_exact_mut(run.dimensions)
+                .zip(run.data.chunks_exact(run.dimensions))
+            {
+                for (value, &original) in point.iter_mut().zip(original).take(jittered_dimensions) {
+                    *value = original + (rng.gaussian() * magnitude) as PointScalar;
+                }
+            }
+            let (clusters, _) =
+                self.cluster_data(&run.parameters, &data, run.dimensions, false, None)?;
+            let mut stable = vec![true; point_count];
+            for index in analysis::changed_points(&run.clusters, &clusters, point_count) {
+                stable[index] = false;
+            }
+            for (kept, stable) in kept.iter_mut().zip(stable) {
+                *kept += stable as usize;
+            }
+        }
+        Ok(kept
+            .into_iter()
+            .map(|kept| kept as f32 / runs as f32)
+            .collect())
+    }
+
+    /// Returns the members of each cluster of the most recent clustering with `category`, excluding the noise group.
+    fn category_members(
+        &self,
+        category: fuzzy_dbscan::Category,
+    ) -> Result<IndexGroups, ClusterError> {
+        let run = self.last_run()?;
+        let mut groups = IndexGroups {
+            offsets: vec![0],
+            indices: Vec::new(),
+        };
+        for cluster in run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+        {
+            let start = groups.indices.len();
+            groups.indices.extend(
+                cluster
+                    .iter()
+                    .filter(|assignment| assignment.category == category)
+                    .map(|assignment| assignment.index as u32),
+            );
+            groups.indices[start..].sort_unstable();
+            groups.offsets.push(groups.indices.len() as u32);
+        }
+        Ok(groups)
+    }
+
+    /// Returns the indices of the noise points of the most recent clustering.
+    fn noise_points(&self) -> Result<Vec<u32>, ClusterError> {
+        let run = self.last_run()?;
+        let mut indices: Vec<u32> = match run.clusters.last() {
+            Some(cluster) if analysis::is_noise(cluster) => cluster
+                .iter()
+                .map(|assignment| assignment.index as u32)
+                .collect(),
+            _ => Vec::new(),
+        };
+        indices.sort_unstable();
+        Ok(indices)
+    }
+
+    /// Counts the clusters and noise of the points of the most recent clustering for each of `eps` at once.
+    fn sweep_eps(
+        &mut self,
+        eps: &[f64],
+        pts_min: f64,
+        pts_max: f64,
+    ) -> Result<EpsSweep, ClusterError> {
+        let point_count = self.last_run()?.point_count();
+        if let Some(index) = eps.iter().position(|eps| eps.is_nan()) {
+            return Err(ClusterError::NanEps { index });
+        }
+        if let Some(option) = self.sweep_ignored_option() {
+            return Err(ClusterError::UnsupportedSweepOption { option });
+        }
+        let parameters = fuzzy_dbscan::FuzzyDBSCAN {
+            pts_min,
+            pts_max,
+            ..self.inner.clone()
+        };
+        let all: Vec<usize> = (0..point_count).collect();
+        let levels = Self::eps_levels(&parameters, eps)
+            .into_iter()
+            .map(|level| self.resolve(level, &all))
+            .collect::<Result<Vec<_>, _>>()?;
+        let run = self.last_run()?;
+        let sweep = with_points!(&run.metric, &run.data, run.dimensions, |points| {
+            let points = self.weighted(points);
+            match run.index.as_deref() {
+                Some(index) => {
+                    fuzzy_dbscan::FuzzyDBSCAN::sweep_eps_indexed(&levels, &points, index)
+                }
+                None => fuzzy_dbscan::FuzzyDBSCAN::sweep_eps(&levels, &points),
+            }
+        });
+        Ok(EpsSweep {
+            cluster_counts: sweep
+                .levels
+                .iter()
+                .map(|level| level.cluster_count as u32)
+                .collect(),
+            noise_fractions: sweep
+                .levels
+                .iter()
+                .map(|level| level.noise_count as f32 / point_count.max(1) as f32)
+                .collect(),
+            join_eps: sweep
+                .join_levels
+                .iter()
+                .map(|level| level.map_or(f32::NAN, |level| levels[level].eps_max as f32))
+                .collect(),
+        })
+    }
+
+    /// Returns the name of the first configured option that `sweep_eps` can't apply but `count_clusters` would, and
+    /// which would therefore make their counts differ.
+    fn sweep_ignored_option(&self) -> Option<&'static str> {
+        [
+            (self.sample.is_some(), "a sample"),
+            (self.time_window.is_some(), "a time window"),
+            (self.exclusion_radius > 0.0, "an exclusion radius"),
+            (!self.seeds.is_empty(), "seeds"),
+            (!self.excluded_cores.is_empty(), "excluded core points"),
+            (
+                !self.must_links.is_empty() || !self.cannot_links.is_empty(),
+                "links",
+            ),
+            (self.noise_reassignment.is_some(), "noise reassignment"),
+            (self.min_avg_membership > 0.0, "a minimum mean membership"),
+        ]
+        .into_iter()
+        .find_map(|(set, option)| set.then_some(option))
+    }
+
+    /// Measures the spread of each cluster, excluding the noise group.
+    fn cluster_spread(&self) -> Result<ClusterSpread, ClusterError> {
+        let run = self.last_run()?;
+        let points = run.points();
+        let mut result = ClusterSpread {
+            diameters: Vec::new(),
+            mean_centroid_distances: Vec::new(),
+        };
+        for cluster in run
+            .clusters
+            .iter()
+            .filter(|cluster| !analysis::is_noise(cluster))
+        {
+            let centroid = analysis::centroid(&run.data, run.dimensions, cluster);
+            let centroid = MetricPoint {
+                data: &centroid,
+                metric: &run.metric,
+            };
+            let spread = analysis::spread(&points, &centroid, cluster);
+            result.diameters.push(spread.diameter as f32);
+            result
+                .mean_centroid_distances
+                .push(spread.mean_centroid_distance as f32);
+        }
+        Ok(result)
+    }
+
+    /// Clusters data.
+    ///
+    /// # Return Value
+    /// Returns packed clusters: u16 array of (point count, (index | (category << 14), soft label)+).
+    fn cluster(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, ClusterError> {
+        Ok(pack_clusters(&self.run(&packed_data, dimensions)?.clusters))
+    }
+
+    /// Clusters data and writes the packed result into `out`, returning the number of bytes written.
+    fn cluster_into(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        out: &mut [u8],
+    ) -> Result<usize, ClusterError> {
+        pack_clusters_into_slice(&self.run(packed_data, dimensions)?.clusters, out)
+    }
+
+    /// Clusters data and additionally returns per-assignment details.
+    fn cluster_detailed(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<ClusterResult, ClusterError> {
+        let clusters = &self.run(&packed_data, dimensions)?.clusters;
+        Ok(ClusterResult {
+            packed: pack_clusters(clusters),
+            border_distances: clusters
+                .iter()
+                .flatten()
+                .map(|assignment| assignment.core_distance.map_or(f32::NAN, |d| d as f32))
+                .collect(),
+            border_cores: clusters
+                .iter()
+                .flatten()
+                .map(|assignment| assignment.core_index.map_or(-1, |index| index as i32))
+                .collect(),
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl FuzzyCluster {
+    /// Creates a clustering with the given fuzzy neighborhood radii and densities.
+    ///
+    /// `pts_min` and `pts_max` are thresholds on the fuzzy density of a point: 1 for the point itself plus 1 for
+    /// each neighbor within `eps_min`, and less than 1 for neighbors between `eps_min` and `eps_max`. Since the
+    /// density is fractional, fractional thresholds are meaningful; core labels rise linearly from 0 at `pts_min`
+    /// to 1 at `pts_max`. Use `with_point_counts` for whole numbers of points.
+    #[wasm_bindgen(constructor)]
+    pub fn new(eps_min: f64, eps_max: f64, pts_min: f64, pts_max: f64) -> Self {
+        FuzzyCluster {
+            inner: fuzzy_dbscan::FuzzyDBSCAN {
+                eps_min,
+                eps_max,
+                pts_min,
+                pts_max,
+            },
+            sample: None,
+            exclusion_radius: 0.0,
+            max_clusters: None,
+            timestamps: Vec::new(),
+            time_window: None,
+            point_ids: None,
+            weights: Vec::new(),
+            embedded_weights: false,
+            memory_budget: None,
+            seeds: Vec::new(),
+            excluded_cores: Vec::new(),
+            must_links: Vec::new(),
+            cannot_links: Vec::new(),
+            interleaving: None,
+            half_precision: false,
+            projection: None,
+            categorical_last_dimension: false,
+            scaling: None,
+            metric: Metric::Euclidean,
+            ensemble_metrics: Vec::new(),
+            core_threshold: 0.0,
+            tolerant: false,
+            label_bits: LabelBits::Sixteen,
+            compact_noise: false,
+            min_avg_membership: 0.0,
+            density_weighted_labels: false,
+            noise_reassignment: None,
+            tie_break_seed: None,
+            warnings: Vec::new(),
+            last_run: None,
+            session: None,
+            previous: None,
+            optimal_matching: false,
+            points: None,
+        }
+    }
+
+    /// Creates a clustering whose density thresholds are whole numbers of points, see the constructor.
+    pub fn with_point_counts(eps_min: f64, eps_max: f64, pts_min: u32, pts_max: u32) -> Self {
+        Self::new(eps_min, eps_max, pts_min as f64, pts_max as f64)
+    }
+
+    /// Sets the density thresholds to whole numbers of points.
+    pub fn set_point_counts(&mut self, pts_min: u32, pts_max: u32) {
+        self.inner.pts_min = pts_min as f64;
+        self.inner.pts_max = pts_max as f64;
+    }
+
+    /// The minimum fuzzy neighborhood density, including the point itself.
+    #[wasm_bindgen(getter)]
+    pub fn pts_min(&self) -> f64 {
+        self.inner.pts_min
+    }
+
+    /// The maximum fuzzy neighborhood density, including the point itself.
+    #[wasm_bindgen(getter)]
+    pub fn pts_max(&self) -> f64 {
+        self.inner.pts_max
+    }
+
+    /// Returns a Float32Array with the core point label for `samples` evenly spaced fuzzy neighbor counts from 0 to
+    /// `pts_max`, e.g. to plot how the density thresholds translate into labels. A point with neighbor count `n`
+    /// has density `1 + n`, as it counts itself; its label is 0 below `pts_min`, rises linearly and is 1 from
+    /// `pts_max` on. Only depends on the configured `pts_min` and `pts_max`, not on any data.
+    pub fn core_label_curve(&self, samples: usize) -> Vec<f32> {
+        let step = self.inner.pts_max / samples.saturating_sub(1).max(1) as f64;
+        (0..samples)
+            .map(|sample| self.inner.density_label(1.0 + sample as f64 * step) as f32)
+            .collect()
+    }
+
+    /// Only clusters every `step`-th point (starting with the first) until the sample is cleared.
+    ///
+    /// Points that are not sampled are absent from the result; indices still refer to the full input.
+    pub fn set_sample_every(&mut self, step: usize) {
+        self.sample = Some(Sample::Every(step));
+    }
+
+    /// Only clusters a uniform random sample of `count` points until the sample is cleared.
+    ///
+    /// The sample is determined by `seed`, so the same seed and point count always select the same points.
+    /// Points that are not sampled are absent from the result; indices still refer to the full input.
+    pub fn set_sample_random(&mut self, count: usize, seed: u32) {
+        self.sample = Some(Sample::Random { count, seed });
+    }
+
+    /// Clusters all points again, e.g. to refine a preview.
+    pub fn clear_sample(&mut self) {
+        self.sample = None;
+    }
+
+    /// Collapses points within `radius` of an earlier point into that representative before clustering, so that
+    /// duplicates don't inflate the neighborhood density. Every point still receives its representative's
+    /// assignments. A radius of 0 (the default) disables this.
+    pub fn set_exclusion_radius(&mut self, radius: f64) {
+        self.exclusion_radius = radius;
+    }
+
+    /// Limits clusterings to at most `max_clusters` clusters (not counting noise), e.g. for a legend with limited
+    /// slots. A limit of 0 disables this (the default).
+    ///
+    /// If the configured parameters produce too many clusters, `eps_min` and `eps_max` are scaled up by the same
+    /// factor, found by bisection, until close clusters merge. If no eps works, the smallest clusters are marked
+    /// as noise instead. `resolved_eps_min` and `resolved_eps_max` report the eps values that were used.
+    /// Stepped clusterings (`start_clustering`) are not limited.
+    pub fn set_max_clusters(&mut self, max_clusters: usize) {
+        self.max_clusters = (max_clusters > 0).then_some(max_clusters);
+    }
+
+    /// The `eps_min` that the most recent clustering used, after tolerant fixes and tuning for `set_max_clusters`.
+    pub fn resolved_eps_min(&self) -> Result<f64, JsValue> {
+        self.last_run()
+            .map(|run| run.parameters.eps_min)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// The `eps_max` that the most recent clustering used, after tolerant fixes and tuning for `set_max_clusters`.
+    pub fn resolved_eps_max(&self) -> Result<f64, JsValue> {
+        self.last_run()
+            .map(|run| run.parameters.eps_max)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Releases the memory held for follow-up queries and stepped clustering, i.e. the copied points and result
+    /// of the most recent clustering, a clustering in progress and its warnings. The configured parameters and
+    /// options stay, so the instance can be reused with fresh data; follow-up queries such as `medoids` fail until
+    /// the next clustering.
+    ///
+    /// Also releases the points of `set_points` and their spatial index. Neighborhoods that clusterings with
+    /// several eps levels share between the levels are released when the clustering ends, so there is nothing else
+    /// to release. Use `free()` to release the whole instance.
+    pub fn clear_cache(&mut self) {
+        self.last_run = None;
+        self.session = None;
+        self.points = None;
+        self.warnings = Vec::new();
+    }
+
+    /// Limits the memory a clustering may use to about `bytes`, e.g. for large inputs on memory-limited devices.
+    /// A budget of 0 removes the limit (the default).
+    ///
+    /// With a budget, neighborhoods are always computed on demand rather than cached as a neighbor graph, trading
+    /// recomputation for memory that grows only linearly with the point count: clusterings with several eps levels
+    /// (`cluster_multilevel` and the hierarchies) search the neighborhoods of each level again instead of sharing
+    /// those of the largest eps between the levels. Clustering estimates its peak memory (the copied points plus
+    /// their spatial index and the clustering state, or plus the check for duplicate points if that needs more)
+    /// before starting and fails with an insufficient memory error if that exceeds the budget, instead of running
+    /// out of wasm memory midway. The estimate doesn't include the input buffer or the returned result.
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = (bytes > 0).then_some(bytes);
+    }
+
+    /// Sets a timestamp per input point for `set_time_window`, e.g. for a scatterplot animated over time.
+    pub fn set_timestamps(&mut self, timestamps: Vec<f64>) {
+        self.timestamps = timestamps;
+    }
+
+    /// Only clusters points whose timestamp is between `start` and `end` (inclusive) until the window is cleared.
+    /// As the window slides, only the window needs to be set again before the next clustering.
+    ///
+    /// Points outside the window are absent from the result; indices still refer to the full input. Clustering
+    /// fails unless there is one timestamp per input point. Like sampling, the window doesn't apply to stepped
+    /// clusterings. Distances are not cached between clusterings, so points that stay in the window are measured
+    /// again.
+    pub fn set_time_window(&mut self, start: f64, end: f64) {
+        self.time_window = Some((start, end));
+    }
+
+    /// Clusters points regardless of their timestamps again (the default).
+    pub fn clear_time_window(&mut self) {
+        self.time_window = None;
+    }
+
+    /// Sets a stable external id per input point (e.g. a database key) from a Uint32Array. Until the ids are
+    /// cleared, `cluster_csr` also reports each member by its id, see `CsrClusters.ids`.
+    ///
+    /// Clustering fails unless there is one id per input point.
+    pub fn set_point_ids(&mut self, ids: Vec<u32>) {
+        self.point_ids = Some(ids.into_iter().map(i64::from).collect());
+    }
+
+    /// Like `set_point_ids`, but with 64-bit ids from a BigInt64Array.
+    pub fn set_point_ids_bigint(&mut self, ids: Vec<i64>) {
+        self.point_ids = Some(ids);
+    }
+
+    /// Reports members only by their positional index again (the default).
+    pub fn clear_point_ids(&mut self) {
+        self.point_ids = None;
+    }
+
+    /// Sets a weight per input point, e.g. the number of records an aggregated point stands for. A point counts
+    /// `weight` times towards densities: its own and those of its neighbors, so heavy points become core points
+    /// sooner and give their neighbors higher labels. Weights must be finite and non-negative; a weight of 1 is
+    /// the unweighted behavior.
+    ///
+    /// Clustering fails unless there is one weight per input point.
+    pub fn set_point_weights(&mut self, weights: Vec<f32>) {
+        self.weights = weights;
+    }
+
+    /// Counts every point once again (the default).
+    pub fn clear_point_weights(&mut self) {
+        self.weights = Vec::new();
+    }
+
+    /// Reads the input of clusterings and queries as points with `dimensions + 1` scalars, whose last scalar is
+    /// the weight of the point (see `set_point_weights`), e.g. `[x0, y0, w0, x1, y1, w1, ...]` for two
+    /// dimensions. `dimensions` stays the number of coordinates. The length of the data must be divisible by
+    /// `dimensions + 1`, and with interleaving the weight follows the coordinates in each record.
+    ///
+    /// Clusterings replace the point weights with the embedded ones, and `update_points` replaces those of the
+    /// moved points. Turning embedded weights off clears the weights.
+    pub fn set_embedded_weights(&mut self, embedded: bool) {
+        if self.embedded_weights && !embedded {
+            self.weights = Vec::new();
+        }
+        self.embedded_weights = embedded;
+    }
+
+    /// Seeds clusters with points that are known to belong together: point `indices[k]` gets hint `hints[k]`, and
+    /// the points with the same hint start one cluster that grows by density from there. Until the seeds are
+    /// cleared, seeded clusters come first in the result, ordered by hint.
+    ///
+    /// Hints take precedence over density: every seed belongs to its hint's cluster (as a border point with label
+    /// 0 if no dense point of the cluster is near), and seeds with different hints are never merged, even if
+    /// their regions are density-connected; points in between then belong to several clusters. Seeds left out by
+    /// sampling are ignored.
+    pub fn set_seeds(&mut self, indices: Vec<u32>, hints: Vec<u32>) -> Result<(), JsValue> {
+        if indices.len() != hints.len() {
+            return Err(JsValue::from(format!(
+                "{}",
+                ClusterError::LengthMismatch {
+                    a: indices.len(),
+                    b: hints.len(),
+                }
+            )));
+        }
+        self.seeds = indices
+            .into_iter()
+            .map(|index| index as usize)
+            .zip(hints)
+            .collect();
+        Ok(())
+    }
+
+    /// Clusters without seeds again (the default).
+    pub fn clear_seeds(&mut self) {
+        self.seeds.clear();
+    }
+
+    /// Bars the points at `indices` from becoming core points, e.g. known artifacts that shouldn't seed spurious
+    /// clusters.
+    ///
+    /// This only changes the core point test, not densities: an excluded point is never a core point, however
+    /// many neighbors it has within the fuzzy thresholds, but it still counts towards the density of its
+    /// neighbors. It joins the clusters of core points within `eps_max` as a border point and is noise otherwise.
+    /// With an exclusion radius, a group of near-duplicates is excluded if any of its points is.
+    pub fn set_excluded_cores(&mut self, indices: Vec<u32>) {
+        self.excluded_cores = indices.into_iter().map(|index| index as usize).collect();
+    }
+
+    /// Lets every point become a core point again (the default).
+    pub fn clear_excluded_cores(&mut self) {
+        self.excluded_cores.clear();
+    }
+
+    /// Forces point `a[k]` and point `b[k]` into the same cluster for every `k`, e.g. from user feedback that
+    /// they belong together. Links are transitive: whenever a cluster reaches a point, all points linked with it
+    /// join the cluster too, as border points if they aren't dense enough to be core points (with label 0 if no
+    /// core point of the cluster is near). Replaces earlier must-links; fails if the arrays differ in length.
+    pub fn set_must_links(&mut self, a: Vec<u32>, b: Vec<u32>) -> Result<(), JsValue> {
+        self.must_links = index_pairs(a, b).map_err(|err| JsValue::from(format!("{}", err)))?;
+        Ok(())
+    }
+
+    /// Keeps point `a[k]` and point `b[k]` out of the same cluster for every `k`: a cluster that already holds
+    /// one of them doesn't grow to the other (nor to the points must-linked with it), even if they are
+    /// density-connected. Later clusters don't expand through the core points of earlier ones, so the blocked
+    /// point ends up in a separate, smaller cluster or is noise, instead of in a copy of the first cluster.
+    ///
+    /// Cannot-links between must-linked points can't be satisfied, and border points, which may be in several
+    /// clusters, can share one with a cannot-linked point. Replaces earlier cannot-links; fails if the arrays
+    /// differ in length.
+    pub fn set_cannot_links(&mut self, a: Vec<u32>, b: Vec<u32>) -> Result<(), JsValue> {
+        self.cannot_links = index_pairs(a, b).map_err(|err| JsValue::from(format!("{}", err)))?;
+        Ok(())
+    }
+
+    /// Removes all must-links and cannot-links (the default).
+    pub fn clear_links(&mut self) {
+        self.must_links.clear();
+        self.cannot_links.clear();
+    }
+
+    /// Reads input as interleaved data: each point occupies `stride` scalars, and its `dimensions` coordinates
+    /// start at `offset` within them. The remaining scalars, e.g. colors or sizes in a vertex buffer, are ignored.
+    ///
+    /// The buffer must hold a whole number of strides. A stride of 0 reads tightly packed data again (the default).
+    pub fn set_interleaved(&mut self, stride: usize, offset: usize) {
+        self.interleaving = (stride > 0).then_some((stride, offset));
+    }
+
+    /// Reads input as IEEE half-precision floats (e.g. from a Uint16Array filled with f16 bits) instead of
+    /// Float32Array data, which halves the transfer size when coordinates don't need more precision. Values are
+    /// widened to 32 bits before clustering.
+    ///
+    /// Dimensions and interleaving strides count half-floats, so the buffer must hold a whole number of points
+    /// of 2-byte scalars.
+    pub fn set_half_precision(&mut self, half_precision: bool) {
+        self.half_precision = half_precision;
+    }
+
+    /// Projects points onto `target_dimensions` random Gaussian directions before clustering, e.g. to make
+    /// density clustering work on high-dimensional feature vectors. The projection is determined by `seed` and
+    /// roughly preserves distances, so `eps` values stay meaningful. Indices still refer to the input points.
+    ///
+    /// The metric then measures projected points; a target of 0 disables the projection (the default). Clustering
+    /// fails if the last dimension is categorical, see `set_categorical_last_dimension`.
+    pub fn set_random_projection(&mut self, target_dimensions: usize, seed: u32) {
+        self.projection = (target_dimensions > 0).then_some(RandomProjection {
+            target_dimensions,
+            seed: seed as u64,
+        });
+    }
+
+    /// Treats the last dimension as a category (e.g. a class of otherwise spatial points), so clusters never mix
+    /// categories: points with different values in it are infinitely far apart, and the configured metric measures
+    /// points of the same category over the other dimensions. This is like clustering each category separately
+    /// with the same parameters, in one call. Applies to the current and later metrics until it is disabled.
+    ///
+    /// Categories must be whole numbers from 0 to 65535; clustering fails otherwise. Scaling keeps categories
+    /// apart, but a random projection mixes them with the other dimensions, so clustering fails if both are set.
+    pub fn set_categorical_last_dimension(&mut self, enabled: bool) {
+        let metric = match std::mem::take(&mut self.metric) {
+            Metric::Categorical { inner } => *inner,
+            metric => metric,
+        };
+        self.categorical_last_dimension = enabled;
+        self.use_metric(metric);
+    }
+
+    /// Standardizes each dimension before clustering: subtracts its mean and divides by its standard deviation, so
+    /// dimensions with different units count alike and eps is in standard deviations. The statistics are computed
+    /// from each clustered dataset and applied to queries against its clustering (e.g. `assign_queries`). This
+    /// happens before the random projection, if any.
+    pub fn set_z_score_scaling(&mut self) {
+        self.scaling = Some(Scaling::ZScore);
+    }
+
+    /// Scales each dimension to the range 0 to 1 before clustering, like `set_z_score_scaling`.
+    pub fn set_min_max_scaling(&mut self) {
+        self.scaling = Some(Scaling::MinMax);
+    }
+
+    /// Centers each dimension on its median and divides it by its interquartile range before clustering, like
+    /// `set_z_score_scaling`. Unlike the mean and standard deviation or the range, these barely move with
+    /// outliers, so heavy-tailed measurements are scaled like their bulk. Dimensions with an interquartile range of
+    /// 0 are only centered. Non-finite coordinates are left out of the statistics for every scaling.
+    pub fn set_robust_scaling(&mut self) {
+        self.scaling = Some(Scaling::Robust);
+    }
+
+    /// Clusters coordinates as they are again (the default).
+    pub fn clear_scaling(&mut self) {
+        self.scaling = None;
+    }
+
+    /// Measures distances with the Euclidean metric over all dimensions (the default).
+    pub fn set_euclidean_metric(&mut self) {
+        self.use_metric(Metric::Euclidean);
+    }
+
+    /// Measures distances as `alpha * spatial + (1 - alpha) * feature`, where `spatial` is the Euclidean
+    /// distance of the first `split` dimensions (e.g. screen position) and `feature` that of the remaining ones.
+    ///
+    /// Clustering fails if points have fewer than `split` dimensions.
+    pub fn set_blended_metric(&mut self, split: usize, alpha: f64) {
+        self.use_metric(Metric::Blended { split, alpha });
+    }
+
+    /// Measures Euclidean distances in which dimension `i` wraps around after `periods[i]`, e.g. 360 for angles
+    /// in degrees or 24 for hours of the day, so 359° is close to 1°. A period of 0 or NaN leaves a dimension
+    /// regular.
+    ///
+    /// Clustering fails with a length mismatch error unless points have exactly one dimension per period.
+    pub fn set_periodic_metric(&mut self, periods: Vec<f32>) {
+        self.use_metric(Metric::Periodic { periods });
+    }
+
+    /// Interprets points as polar coordinates (radius, angle in radians) and measures the planar distance between
+    /// them, e.g. for radial scatterplots.
+    ///
+    /// Clustering fails unless points have exactly 2 dimensions.
+    pub fn set_polar_metric(&mut self) {
+        self.use_metric(Metric::Polar);
+    }
+
+    /// Measures distances as `1 - r`, where `r` is the Pearson correlation between the coordinates of two points,
+    /// so points with similar profiles are close regardless of magnitude. Distances range from 0 to 2, and a point
+    /// whose coordinates are all equal has distance 1 to every point.
+    pub fn set_correlation_metric(&mut self) {
+        self.use_metric(Metric::Correlation);
+    }
+
+    /// Measures distances between binary feature vectors (e.g. tags) as the Jaccard distance
+    /// `1 - |A ∩ B| / |A ∪ B|`, where a point's set holds the dimensions in which its coordinate is at least 0.5.
+    /// Two points without any such dimension have distance 0.
+    pub fn set_jaccard_metric(&mut self) {
+        self.use_metric(Metric::Jaccard);
+    }
+
+    /// Measures distances between points whose coordinates are histograms (e.g. normalized distributions over
+    /// bins) as their Jensen-Shannon divergence, the symmetric average of the KL divergences to their mixture, in
+    /// bits, so distances range from 0 to 1.
+    ///
+    /// Coordinates must be non-negative. Each is increased by a smoothing epsilon of 1e-9 so that empty bins don't
+    /// produce `log(0)`, and every point is normalized to sum to 1, so raw counts work as well.
+    pub fn set_jensen_shannon_metric(&mut self) {
+        self.use_metric(Metric::JensenShannon);
+    }
+
+    /// Measures distances with a Gaussian kernel, `sqrt(2 - 2 * exp(-gamma * d^2))` for the Euclidean distance `d`,
+    /// which compresses large distances so that clusters are defined by local structure, e.g. for data on a
+    /// manifold. Larger `gamma` values make the neighborhood more local.
+    ///
+    /// Distances range from 0 to `sqrt(2)`, so eps must be given on this scale: to match a Euclidean eps `e`,
+    /// use `sqrt(2 - 2 * exp(-gamma * e * e))`, which is about `sqrt(2 * gamma) * e` for small `e`. Every point is
+    /// within an eps of `sqrt(2)` or more.
+    pub fn set_kernel_metric(&mut self, gamma: f64) {
+        self.use_metric(Metric::Kernel { gamma });
+    }
+
+    /// Adds the currently configured metric (set with one of the `set_*_metric` methods) to the metrics of
+    /// `cluster_ensemble`, e.g. `set_euclidean_metric(); add_ensemble_metric(); set_correlation_metric();
+    /// add_ensemble_metric()`. Later metric changes don't affect the added metrics.
+    pub fn add_ensemble_metric(&mut self) {
+        self.ensemble_metrics.push(self.metric.clone());
+    }
+
+    /// Removes all metrics added with `add_ensemble_metric`.
+    pub fn clear_ensemble_metrics(&mut self) {
+        self.ensemble_metrics.clear();
+    }
+
+    /// Sets the width of the soft labels in the results of `cluster_with_header` and `cluster_without_noise` to 8
+    /// or 16 bits (the default). Fails for other widths.
+    ///
+    /// With 8 bits, the flags word of the header has bit 1 set, and each cluster is (point count, (index |
+    /// (category << 14))+, labels) where the labels are bytes in the order of the indices, scaled by 255, two per
+    /// word (first in the low byte) and padded with a zero byte to a whole word. This is a quarter smaller than the
+    /// 16-bit layout, whose labels are scaled by 65535. `cluster` always uses 16-bit labels.
+    pub fn set_label_bits(&mut self, bits: u32) -> Result<(), JsValue> {
+        self.label_bits = LabelBits::new(bits).map_err(|err| JsValue::from(format!("{}", err)))?;
+        Ok(())
+    }
+
+    /// Dissolves clusters whose mean label is below `min_avg_membership` into noise, e.g. 0.5 to drop clusters of
+    /// mostly low-confidence border points. Their points become noise unless they also belong to another cluster.
+    /// Unlike `set_max_clusters`, which keeps the largest clusters, this targets confidence. It is applied after
+    /// `set_density_weighted_labels` and `set_noise_reassignment`, so their labels count. The default of 0 keeps
+    /// every cluster.
+    pub fn set_min_avg_membership(&mut self, min_avg_membership: f64) {
+        self.min_avg_membership = min_avg_membership;
+    }
+
+    /// Scales the labels of border points by how dense their neighborhood is, so border points in dense regions
+    /// get higher labels than those in sparse regions at the same distance to a core point, e.g. for opacity that
+    /// reflects confidence. Off by default, where a border point's label only depends on its distance to the
+    /// cluster's core points.
+    ///
+    /// With weighting, a border point's label is `label * min(1, density / pts_max)`: the usual label (the smallest
+    /// `min(core_label, mu(d))` over the cluster's core points within `eps_max`, where `mu(d)` falls linearly from
+    /// 1 at `eps_min` to 0 at `eps_max`) times its own fuzzy density (1 for the point itself plus the `mu(d)` of
+    /// each neighbor, as for core points) relative to `pts_max`. Like the densities of core points, it counts only
+    /// the points the clustering saw: the sample within the time window, with one point per group of
+    /// near-duplicates. A border point's density is usually at most `pts_min`, since a denser point would be a
+    /// core point, so the factor is below 1; only points excluded from becoming core points (see
+    /// `set_excluded_cores`) can be denser and keep their label. Core point labels don't change. This is applied before the core threshold, so core points categorized as border points by it
+    /// keep their labels.
+    pub fn set_density_weighted_labels(&mut self, enabled: bool) {
+        self.density_weighted_labels = enabled;
+    }
+
+    /// Adds a final pass to clusterings that attaches noise to the nearest cluster if one of its core points is
+    /// within `eps_max * factor`, e.g. a factor of 1.5 for points just outside a cluster. Off by default; factors
+    /// of at most 1 have no effect, as noise is always farther than `eps_max` from every core point.
+    ///
+    /// Reassigned points have category 3 in the packed result (`"reassigned"` elsewhere) and a small label that
+    /// falls linearly with the distance `d` to the nearest core point: `(eps_max * factor - d) / (eps_max * factor
+    /// - eps_min)`, at most the core point's label. They count as cluster members, not noise, in every query.
+    pub fn set_noise_reassignment(&mut self, factor: f64) {
+        self.noise_reassignment = Some(factor);
+    }
+
+    /// Leaves noise unassigned again (the default).
+    pub fn clear_noise_reassignment(&mut self) {
+        self.noise_reassignment = None;
+    }
+
+    /// Sets the soft label a core point needs to be categorized as a core point rather than a border point, e.g. to
+    /// tune a solid/hollow marker split. This only changes categories, never cluster membership or labels.
+    ///
+    /// Clustering lets points with a fuzzy density of at least `pts_min` expand clusters, and gives them a label
+    /// that rises linearly from 0 at `pts_min` to 1 at `pts_max`. With a threshold `t`, only those with a label of
+    /// at least `t`, i.e. a density of at least `pts_min + t * (pts_max - pts_min)`, are categorized as core
+    /// points; the others are categorized as border points but still expand their cluster. The default of 0 keeps
+    /// every dense point a core point, and thresholds above 1 make every point a border point. Queries based on
+    /// core points, such as `assign_queries`, use the resulting categories.
+    pub fn set_core_threshold(&mut self, threshold: f64) {
+        self.core_threshold = threshold;
+    }
+
+    /// Chooses how invalid parameters are handled.
+    ///
+    /// In strict mode (the default) clustering fails with an error. In tolerant mode the parameters are fixed for
+    /// the run and a warning is recorded (see `last_run_warnings`):
+    /// - an `eps_min` greater than `eps_max`, e.g. from crossed slider handles, is swapped.
+    /// - a `pts_min` or `pts_max` below the smallest density of a point is set to it. The density of a point
+    ///   counts the point itself with its weight, so it is never below the smallest weight (1 without weights, see
+    ///   `set_point_weights`); smaller thresholds would make every point a core point.
+    /// - a `pts_min` of at least the total weight of the points to cluster (their number without weights, after
+    ///   sampling and the time window) is set to that weight minus the largest weight, if `pts_max` is larger
+    ///   still. The density of a point is at most the total weight, so no point could exceed such a `pts_min`
+    ///   and every point would be noise. A `pts_max` above the total weight only keeps core labels below 1, so it
+    ///   is left as is.
+    pub fn set_tolerant(&mut self, tolerant: bool) {
+        self.tolerant = tolerant;
+    }
+
+    /// Gathers all noise into one trailing group that is present even if no point is noise, so decoders can treat
+    /// the last group of the packed result as noise without checking categories. Then, with `n` real clusters, the
+    /// noise group is group `n` of `cluster` and `last_result`, and outputs with per-point cluster ids such as
+    /// `flat_labels` give noise the reserved id -1. Off by default, where the noise group is left out when empty.
+    ///
+    /// This also applies to `cluster_with_header` (whose cluster count then includes the empty group),
+    /// `cluster_csr`, `cluster_hierarchy`, `cluster_multilevel` and `recluster_noise`, but not to stepped
+    /// clusterings.
+    pub fn set_compact_noise(&mut self, compact_noise: bool) {
+        self.compact_noise = compact_noise;
+    }
+
+    /// Breaks ties in `flat_labels` between clusters in which a point has the same highest membership randomly
+    /// instead of choosing the earlier cluster, e.g. to measure how stable an analysis is over many seeds.
+    ///
+    /// The same seed always picks the same clusters for the same clustering.
+    pub fn set_random_tie_break(&mut self, seed: u32) {
+        self.tie_break_seed = Some(seed);
+    }
+
+    /// Breaks ties in `flat_labels` by choosing the earlier cluster again (the default).
+    pub fn set_deterministic_tie_break(&mut self) {
+        self.tie_break_seed = None;
+    }
+
+    #[wasm_bindgen(js_name = "cluster")]
+    pub fn cluster_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.cluster(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster`, but writes the packed result into the start of `out` (e.g. a view into a preallocated
+    /// buffer) and returns the number of bytes written, instead of allocating a new array.
+    ///
+    /// The result needs 2 bytes per cluster (including the noise group) plus 4 bytes per assignment. Border points
+    /// have one assignment per cluster they belong to, so the size isn't known before clustering. If `out` is too
+    /// small, nothing is written and the error names the required size; the clustering is still kept, so
+    /// `last_result_size` returns the size to allocate and the result is available from `last_result`.
+    #[wasm_bindgen(js_name = "cluster_into")]
+    pub fn cluster_into_js(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        out: &mut [u8],
+    ) -> Result<usize, JsValue> {
+        self.cluster_into(packed_data, dimensions, out)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Sets points to cluster repeatedly with `recluster`, e.g. with different parameters in an interactive
+    /// workflow, and to query with `neighbors_of`. The points are read, scaled and projected like the input of
+    /// `cluster`, and measured with the current metric.
+    ///
+    /// Setting the points builds a spatial index of them, which depends only on the points, so every `recluster`
+    /// and `neighbors_of`, and `sweep_eps` and `count_clusters` after a `recluster`, reuse it instead of building
+    /// it again. The index is rebuilt only when the points change: by setting points again or by `update_points`
+    /// of a clustering of them, which moves the set points too; `clear_cache` releases it. Changing the metric
+    /// afterwards makes reclusterings measure with the new metric without the index. The index speeds up
+    /// neighborhood searches of Euclidean points (by sorting one-dimensional points or with a k-d tree of
+    /// others); for other metrics, searches compare each point with all others either way.
+    #[wasm_bindgen(js_name = "set_points")]
+    pub fn set_points_js(&mut self, packed_data: &[u8], dimensions: usize) -> Result<(), JsValue> {
+        self.set_points(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters the points of `set_points` with the current parameters and options and returns the packed result
+    /// like `cluster`, which becomes the most recent clustering. Warnings about the points are reported again.
+    #[wasm_bindgen(js_name = "recluster")]
+    pub fn recluster_js(&mut self) -> Result<Vec<u8>, JsValue> {
+        self.recluster()
+            .map(|run| pack_clusters(&run.clusters))
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters the points of `set_points` like `recluster`, but with the given parameters instead of the
+    /// configured ones (see `new`), and without changing the instance: the result doesn't become the most recent
+    /// clustering, and its warnings are returned with it instead of replacing `last_run_warnings`. So workers can
+    /// cluster the points with different parameters concurrently on one shared instance.
+    #[wasm_bindgen(js_name = "recluster_with")]
+    pub fn recluster_with_js(
+        &self,
+        eps_min: f64,
+        eps_max: f64,
+        pts_min: f64,
+        pts_max: f64,
+    ) -> Result<ReclusterResult, JsValue> {
+        let dbscan = fuzzy_dbscan::FuzzyDBSCAN {
+            eps_min,
+            eps_max,
+            pts_min,
+            pts_max,
+        };
+        let mut warnings = Vec::new();
+        self.recluster_with(dbscan, &mut warnings)
+            .map(|run| ReclusterResult {
+                packed: pack_clusters(&run.clusters),
+                warnings,
+            })
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Uint32Array with the points of `set_points` within `eps` of the point at `index` (excluding the
+    /// point itself), sorted by index, e.g. to highlight the neighborhood of a hovered point.
+    #[wasm_bindgen(js_name = "neighbors_of")]
+    pub fn neighbors_of_js(&self, index: usize, eps: f64) -> Result<Vec<u32>, JsValue> {
+        self.neighbors_of(index, eps)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Starts a clustering that runs in steps instead of one blocking call, e.g. to spread it across animation
+    /// frames. Advance it with `continue_clustering` or `step`.
+    ///
+    /// The session clusters all points with the current parameters and metric; sampling and the exclusion radius
+    /// don't apply. Starting a new session discards the previous one.
+    #[wasm_bindgen(js_name = "start_clustering")]
+    pub fn start_clustering_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<(), JsValue> {
+        self.start_clustering(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Advances the current session by at most `max_points` neighborhood queries and returns whether it is done.
+    ///
+    /// When it is done, the session ends and its result becomes the most recent clustering, available from
+    /// `last_result` and the other queries.
+    #[wasm_bindgen(js_name = "continue_clustering")]
+    pub fn continue_clustering_js(&mut self, max_points: usize) -> Result<bool, JsValue> {
+        self.continue_clustering(max_points)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `continue_clustering`, but also returns the fraction of points visited so far, e.g. for a progress bar
+    /// in a `requestAnimationFrame` loop:
+    /// ```js
+    /// cluster.start_clustering(data, 2);
+    /// const frame = () => {
+    ///     const { done, progress } = cluster.step(2000);
+    ///     bar.value = progress;
+    ///     if (done) render(cluster.last_result());
+    ///     else requestAnimationFrame(frame);
+    /// };
+    /// requestAnimationFrame(frame);
+    /// ```
+    #[wasm_bindgen(js_name = "step")]
+    pub fn step_js(&mut self, max_points: usize) -> Result<StepResult, JsValue> {
+        self.step(max_points)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Serializes the current session, including its points, into a byte buffer for `resume_from_state`.
+    ///
+    /// The parameters aren't included: resume on an instance configured in the same way.
+    #[wasm_bindgen(js_name = "serialize_state")]
+    pub fn serialize_state_js(&self) -> Result<Vec<u8>, JsValue> {
+        self.serialize_state()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Replaces the current session with one serialized by `serialize_state`, possibly in another worker.
+    ///
+    /// Continuing it yields the same result as an uninterrupted clustering.
+    #[wasm_bindgen(js_name = "resume_from_state")]
+    pub fn resume_from_state_js(&mut self, state: &[u8]) -> Result<(), JsValue> {
+        self.resume_from_state(state)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the most recent clustering in the packed format of `cluster`.
+    #[wasm_bindgen(js_name = "last_result")]
+    pub fn last_result_js(&self) -> Result<Vec<u8>, JsValue> {
+        self.last_run()
+            .map(|run| pack_clusters(&run.clusters))
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the number of bytes of the most recent clustering in the packed format of `cluster`, e.g. to size
+    /// the buffer of `cluster_into`.
+    #[wasm_bindgen(js_name = "last_result_size")]
+    pub fn last_result_size_js(&self) -> Result<usize, JsValue> {
+        self.last_run()
+            .map(|run| packed_size(&run.clusters))
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns an array of strings describing non-fatal problems found during the most recent clustering,
+    /// such as non-finite coordinates, duplicate points, constant dimensions, fewer points than dimensions or one
+    /// dimension for a length that fits 2D or 3D points (which usually means a wrong `dimensions` parameter) or a
+    /// result where more than 80% of the points are noise, which usually means the parameters need adjusting.
+    pub fn last_run_warnings(&self) -> js_sys::Array {
+        self.warnings.iter().map(|w| JsValue::from_str(w)).collect()
+    }
+
+    /// Like `cluster`, but the packed clusters are preceded by a header of u16 words, so the result can be decoded
+    /// in one pass:
+    /// - format version (currently 1)
+    /// - flags: bit 1 marks 8-bit labels (see `set_label_bits`), see also `cluster_without_noise`
+    /// - dimensions per point
+    /// - number of input points as u32 (low word first)
+    /// - number of clusters as u32 (low word first), including the noise group
+    ///
+    /// Indices still have 14 bits, so this throws for more than 16384 points.
+    #[wasm_bindgen(js_name = "cluster_with_header")]
+    pub fn cluster_with_header_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.cluster_with_header(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster_with_header`, but leaves out the noise group, which keeps the result small when most points
+    /// are noise. The flags word has bit 0 set to mark this, and the cluster count only counts real clusters.
+    ///
+    /// Every input point (from 0 to the point count in the header) that no cluster lists is noise. This throws if
+    /// a cluster lists a point at index 16384 or above.
+    #[wasm_bindgen(js_name = "cluster_without_noise")]
+    pub fn cluster_without_noise_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.cluster_without_noise(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster`, but returns the result as a self-describing MessagePack map with the fields `dimensions`,
+    /// `point_count` and `clusters`. Each cluster is an array of `{ index, category, label }` maps, where the
+    /// category is `"core"`, `"border"`, `"noise"` or `"reassigned"` (see `set_noise_reassignment`); the last
+    /// cluster is the noise group.
+    ///
+    /// Only available when the crate is built with the `messagepack` feature.
+    #[cfg(feature = "messagepack")]
+    #[wasm_bindgen(js_name = "cluster_messagepack")]
+    pub fn cluster_messagepack_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.cluster_messagepack(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster`, but returns the clusters in compressed sparse row layout instead of the packed format: parallel
+    /// typed arrays of indices, categories and labels in cluster order, plus where each cluster starts. This suits
+    /// columnar processing and needs no bit unpacking, and has no limit on point indices.
+    #[wasm_bindgen(js_name = "cluster_csr")]
+    pub fn cluster_csr_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<CsrClusters, JsValue> {
+        self.cluster_csr(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster`, but returns an array with one object per input point, in input order, e.g. to bind points to
+    /// DOM elements in a d3 data join:
+    /// ```js
+    /// const points = clustering.cluster_points(data, 2);
+    /// // points[i] = { clusterId: 0, category: "core", membership: 1 }
+    /// ```
+    /// `clusterId` and `membership` are those of `flat_labels` (the point's cluster with the highest membership),
+    /// and `category` is `"core"`, `"border"` or `"reassigned"` in that cluster. Noise points (and points left out
+    /// by sampling) have id -1, category `"noise"` and membership 0.
+    ///
+    /// Creating the objects costs far more than the packed result of `cluster`, so prefer that for large data.
+    #[wasm_bindgen(js_name = "cluster_points")]
+    pub fn cluster_points_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<js_sys::Array, JsValue> {
+        let records = self
+            .cluster_point_records(&packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))?;
+        let (cluster_id, category, membership) = (
+            JsValue::from_str("clusterId"),
+            JsValue::from_str("category"),
+            JsValue::from_str("membership"),
+        );
+        records
+            .into_iter()
+            .map(|(id, point_category, point_membership)| {
+                let record = js_sys::Object::new();
+                js_sys::Reflect::set(&record, &cluster_id, &JsValue::from(id))?;
+                js_sys::Reflect::set(
+                    &record,
+                    &category,
+                    &JsValue::from_str(point_category.name()),
+                )?;
+                js_sys::Reflect::set(&record, &membership, &JsValue::from(point_membership))?;
+                Ok(JsValue::from(record))
+            })
+            .collect()
+    }
+
+    /// Returns a Uint32Array with the original index of each cluster's medoid, the member with the smallest
+    /// total distance to the other members, from the most recent clustering. The noise group has no medoid, so
+    /// entry `i` belongs to the `i`-th cluster of the packed result.
+    ///
+    /// Clusters with more than 512 members only compare an evenly spaced sample of 512 members, which gives an
+    /// approximate medoid.
+    #[wasm_bindgen(js_name = "medoids")]
+    pub fn medoids_js(&self) -> Result<Vec<u32>, JsValue> {
+        self.medoids()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Float32Array with every point's membership in every cluster of the most recent clustering, as a
+    /// row-major matrix with one row per input point and one column per cluster (excluding the noise group, so
+    /// column `i` belongs to the `i`-th cluster of the packed result).
+    ///
+    /// With `normalize`, each point's memberships are scaled to sum to 1, e.g. for pie or stacked encodings.
+    /// Points that are only noise keep all memberships at 0.
+    #[wasm_bindgen(js_name = "memberships")]
+    pub fn memberships_js(&self, normalize: bool) -> Result<Vec<f32>, JsValue> {
+        self.memberships(normalize)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Float32Array with the density of each cluster of the most recent clustering: its number of
+    /// members divided by the volume (area in 2D) of its axis-aligned bounding box, e.g. for a density-based color
+    /// scale. The noise group is excluded, so entry `i` belongs to the `i`-th cluster of the packed result.
+    ///
+    /// Clusters whose bounding box has no volume, e.g. because all members are identical or lie on a line in 2D,
+    /// have infinite density.
+    #[wasm_bindgen(js_name = "cluster_densities")]
+    pub fn cluster_densities_js(&self) -> Result<Vec<f32>, JsValue> {
+        self.cluster_densities()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Labels each cluster of the most recent clustering by the most common value of a categorical attribute among
+    /// its members, e.g. to name clusters in a legend. `attributes` holds one category id per input point.
+    ///
+    /// Ties go to the smaller id. The noise group is excluded, so entry `i` belongs to the `i`-th cluster of the
+    /// packed result.
+    #[wasm_bindgen(js_name = "dominant_attributes")]
+    pub fn dominant_attributes_js(
+        &self,
+        attributes: &[u32],
+    ) -> Result<DominantAttributes, JsValue> {
+        self.dominant_attributes(attributes)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Uint8Array of RGB triplets suggesting a distinct color for each cluster of the most recent
+    /// clustering, so it has 3 entries per cluster. The noise group has no color, so triplet `i` belongs to the
+    /// `i`-th cluster of the packed result.
+    ///
+    /// Hues are spread evenly around the HSL wheel (saturation 65%, lightness 50%) by decreasing cluster size:
+    /// the largest cluster is red. Since clusters are ordered deterministically, the colors are consistent when the
+    /// same data is clustered again.
+    #[wasm_bindgen(js_name = "cluster_colors")]
+    pub fn cluster_colors_js(&self) -> Result<Vec<u8>, JsValue> {
+        self.cluster_colors()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Runs a second pass over the points that the most recent clustering marked as noise, e.g. with a larger `eps`
+    /// or smaller `pts` to find weaker structure, and returns the updated packed result.
+    ///
+    /// The pass uses classic DBSCAN (`eps_min = eps_max = eps`, `pts_min = pts_max = pts`). The new clusters are
+    /// appended after the existing ones, so existing cluster ids stay valid, and points that are still noise form
+    /// the trailing noise group. Follow-up queries such as `medoids` then see the updated result.
+    ///
+    /// Point weights, seeds, excluded core points, constraints and the options that finish a clustering (density
+    /// weighted labels, the core threshold, noise reassignment and the minimum mean membership) apply to the pass
+    /// like to `cluster`, restricted to the noise points. The cluster limit and the exclusion radius don't. A later
+    /// `update_points` clusters all points again with the parameters of the first pass, which discards this pass.
+    #[wasm_bindgen(js_name = "recluster_noise")]
+    pub fn recluster_noise_js(&mut self, eps: f64, pts: f64) -> Result<Vec<u8>, JsValue> {
+        self.recluster_noise(eps, pts)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the number of clusters (without noise) that the points of the most recent clustering form with
+    /// `eps` as `eps_max` (`eps_min` keeps its configured ratio to `eps_max`) and the given densities, e.g. to plot
+    /// the cluster count against eps when looking for an elbow.
+    ///
+    /// This is cheaper than clustering and decoding the result for every eps, since nothing is packed or copied
+    /// to JavaScript. The other options apply as configured, except for `set_max_clusters`, and the most recent
+    /// clustering stays as it is.
+    #[wasm_bindgen(js_name = "count_clusters")]
+    pub fn count_clusters_js(
+        &mut self,
+        eps: f64,
+        pts_min: f64,
+        pts_max: f64,
+    ) -> Result<usize, JsValue> {
+        self.count_clusters(eps, pts_min, pts_max)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Estimates how stable the assignment of each point of the most recent clustering is under small
+    /// perturbations, e.g. to fade out points whose membership is uncertain. Adds Gaussian jitter with standard
+    /// deviation `magnitude` to every coordinate, clusters again with the same parameters, and repeats this `runs`
+    /// times. Returns a Float32Array with, per input point, the fraction of runs in which the point kept its
+    /// clusters: 1 for points that never changed, 0 for points that changed every time.
+    ///
+    /// A jittered cluster counts as the cluster of the most recent clustering that holds most of its members, so
+    /// clusters that merely grow or shrink keep their identity. The jitter is determined by `seed`, applies to the
+    /// points as clustered (after scaling and projection) and spares the category of a categorical metric. The
+    /// other options apply as configured, except for `set_max_clusters`, and the most recent clustering stays as
+    /// it is. With 0 runs, every point is stable.
+    #[wasm_bindgen(js_name = "assignment_stability")]
+    pub fn assignment_stability_js(
+        &self,
+        magnitude: f64,
+        runs: usize,
+        seed: u32,
+    ) -> Result<Vec<f32>, JsValue> {
+        self.assignment_stability(magnitude, runs, seed)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `count_clusters` for each of `eps_values` at once, e.g. to plot the cluster count of a whole eps range
+    /// in an eps-tuning UI. Also returns the fraction of points that are noise at each eps and, for each point, the
+    /// eps at which it joins a cluster.
+    ///
+    /// Distances are computed only once, for the largest eps; the levels then add pairs of points by increasing
+    /// distance and track clusters with a union-find, which is much faster than one `count_clusters` per eps.
+    /// The counts match those of `count_clusters`. Since the sweep clusters all points of the most recent
+    /// clustering with the FuzzyDBSCAN parameters alone, it fails if a sample, a time window, an exclusion radius,
+    /// seeds, excluded core points, links, noise reassignment or a minimum mean membership is set, and if an eps
+    /// value is NaN.
+    #[wasm_bindgen(js_name = "sweep_eps")]
+    pub fn sweep_eps_js(
+        &mut self,
+        eps_values: Vec<f64>,
+        pts_min: f64,
+        pts_max: f64,
+    ) -> Result<EpsSweep, JsValue> {
+        self.sweep_eps(&eps_values, pts_min, pts_max)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns how far each cluster of the most recent clustering spreads out, e.g. for sizing glyphs. The noise
+    /// group is excluded, so entry `i` belongs to the `i`-th cluster of the packed result.
+    ///
+    /// Clusters with more than 1024 members get an approximate diameter: the distance from the member farthest
+    /// from the centroid to the member farthest from that one. It is never too large and at least half the true
+    /// diameter.
+    #[wasm_bindgen(js_name = "cluster_spread")]
+    pub fn cluster_spread_js(&self) -> Result<ClusterSpread, JsValue> {
+        self.cluster_spread()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters data `repetitions` times and returns the mean wall-clock time of a run in milliseconds. Only
+    /// available when built with the `bench` feature.
+    ///
+    /// Combined with `generate_clusters`, this reproduces performance reports without a custom data generator:
+    /// ```js
+    /// const data = generate_clusters(50000, 2, 8, 0.02, 1);
+    /// const ms = new FuzzyCluster(0.01, 0.02, 5, 10).bench_cluster(data, 2, 5);
+    /// ```
+    /// The timer is `Date.now()`, so use enough points or repetitions for runs to take well over a millisecond.
+    #[cfg(feature = "bench")]
+    pub fn bench_cluster(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+        repetitions: u32,
+    ) -> Result<f64, JsValue> {
+        let start = js_sys::Date::now();
+        for _ in 0..repetitions {
+            self.run(&packed_data, dimensions)
+                .map_err(|err| JsValue::from(format!("{}", err)))?;
+        }
+        Ok((js_sys::Date::now() - start) / repetitions.max(1) as f64)
+    }
+
+    /// Classifies new points with the most recent clustering without clustering again: returns an Int32Array with,
+    /// for each point of `packed_data`, the cluster of the nearest core point within `eps_max`, or -1 if there is
+    /// none. Cluster `i` is the `i`-th cluster of the packed result.
+    ///
+    /// The points are read like the clustered ones (interleaving and projection apply) and must have the same
+    /// number of dimensions.
+    #[wasm_bindgen(js_name = "assign_queries")]
+    pub fn assign_queries_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<i32>, JsValue> {
+        self.assign_queries(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Moves the points at `changed_indices` of the most recent clustering to the points of `packed_data` (one per
+    /// index, read like the clustered ones) and updates the clustering, e.g. after dragging points in an editor.
+    /// Returns a Uint32Array with the points whose clusters changed, sorted by index; `last_result` and the other
+    /// queries then return the updated clustering.
+    ///
+    /// The result is the same as clustering the moved points again with the parameters of the most recent
+    /// clustering and the current options. Only the neighborhoods of points within `eps_max` of a moved point, at
+    /// its old or new position, and the clusters they belong to are searched again, unless the clustering used
+    /// sampling, near-duplicate collapsing, a time window, a cluster limit, seeds, excluded core points or
+    /// constraints (or they are set now), was run in steps or had a second pass of `recluster_noise`; then all
+    /// points are clustered again, without the second pass. The spatial index of the points is kept across updates
+    /// and only records which points moved, until so many did that it is rebuilt. Moved points
+    /// are scaled with the statistics of the most recent clustering (see `set_robust_scaling`). If the most recent
+    /// clustering is one of the points of `set_points`, they move as well, so the next `recluster` and
+    /// `neighbors_of` see the moved points.
+    ///
+    /// A cluster of the update counts as the earlier cluster that holds most of its members, so points keep their
+    /// cluster when it grows, shrinks or splits, and change it when they join or leave a cluster or their cluster
+    /// merges into a larger one.
+    #[wasm_bindgen(js_name = "update_points")]
+    pub fn update_points_js(
+        &mut self,
+        changed_indices: Vec<u32>,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<u32>, JsValue> {
+        self.update_points(&changed_indices, packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Uint8Array with 1 for every point of `packed_data` that is a core point and 0 otherwise, in input
+    /// order, e.g. for solid and hollow markers. This only computes neighborhood densities and is cheaper than
+    /// `cluster`, whose core points are the same (sampling and the exclusion radius aside, which don't apply here).
+    ///
+    /// Records warnings about the data like a clustering, but keeps the most recent clustering.
+    #[wasm_bindgen(js_name = "core_mask")]
+    pub fn core_mask_js(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<u8>, JsValue> {
+        self.core_mask(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Uint32Array with the indices of the points of `packed_data` (at most 3 dimensions) sorted along
+    /// the Morton (Z-order) curve, e.g. to reorder a vertex buffer so that points close in space are close in
+    /// memory. Interleaving applies, the projection doesn't.
+    ///
+    /// Each coordinate is scaled to the range of its dimension and quantized to 64 / `dimensions` bits (at most
+    /// 63), and the bits of all dimensions are interleaved into one code per point. Points with the same code keep
+    /// their order.
+    #[wasm_bindgen(js_name = "spatial_order")]
+    pub fn spatial_order_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<Vec<u32>, JsValue> {
+        self.read_input(packed_data, dimensions)
+            .and_then(|data| spatial::morton_order(&data, dimensions))
+            .map(|order| order.into_iter().map(|index| index as u32).collect())
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the distance between points `i` and `j` of `packed_data`, measured with the configured metric (and
+    /// interleaving and projection) like a clustering would measure it, without clustering.
+    #[wasm_bindgen(js_name = "distance_between")]
+    pub fn distance_between_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+        i: u32,
+        j: u32,
+    ) -> Result<f64, JsValue> {
+        self.pairwise_distances(packed_data, dimensions, &[i], &[j])
+            .map(|distances| distances[0] as f64)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Float32Array with the distance between points `indices_a[k]` and `indices_b[k]` of `packed_data`
+    /// for every `k`, measured like `distance_between`. Both index arrays must have the same length.
+    #[wasm_bindgen(js_name = "pairwise_distances")]
+    pub fn pairwise_distances_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+        indices_a: &[u32],
+        indices_b: &[u32],
+    ) -> Result<Vec<f32>, JsValue> {
+        self.pairwise_distances(packed_data, dimensions, indices_a, indices_b)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Finds groups of (near-)duplicate points in `packed_data`, which skew densities, e.g. to decide whether to
+    /// deduplicate before clustering. Points are measured like `distance_between`; a `tolerance` of 0 finds exact
+    /// duplicates.
+    ///
+    /// Each point joins the group of the first earlier point within `tolerance`, like with
+    /// `set_exclusion_radius`, so members are within `tolerance` of the group's first point. Only groups with at
+    /// least two points are returned, ordered by their first point. This compares every point with the first
+    /// point of every group, so it is quadratic for data without duplicates.
+    #[wasm_bindgen(js_name = "find_duplicates")]
+    pub fn find_duplicates_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+        tolerance: f64,
+    ) -> Result<IndexGroups, JsValue> {
+        self.find_duplicates(packed_data, dimensions, tolerance)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the original indices of the border points of each cluster of the most recent clustering, e.g. for
+    /// drawing outlines, as groups in compressed sparse row layout: group `i` belongs to the `i`-th cluster of the
+    /// packed result (the noise group is left out) and may be empty. Points that are border points of several
+    /// clusters are in each of their groups; points reassigned from noise (see `set_noise_reassignment`) are not
+    /// border points.
+    #[wasm_bindgen(js_name = "border_points")]
+    pub fn border_points_js(&self) -> Result<IndexGroups, JsValue> {
+        self.category_members(fuzzy_dbscan::Category::Border)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `border_points`, but returns the core points of each cluster.
+    #[wasm_bindgen(js_name = "core_points")]
+    pub fn core_points_js(&self) -> Result<IndexGroups, JsValue> {
+        self.category_members(fuzzy_dbscan::Category::Core)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Uint32Array with the original indices of the noise points of the most recent clustering, in input
+    /// order.
+    #[wasm_bindgen(js_name = "noise_points")]
+    pub fn noise_points_js(&self) -> Result<Vec<u32>, JsValue> {
+        self.noise_points()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the minimum spanning tree over all points of `packed_data`, measured like `distance_between`, e.g. for
+    /// single-linkage cuts (removing all edges heavier than a threshold leaves the single-linkage clusters) or to
+    /// draw how points connect. The tree has one edge less than there are points, sorted by increasing weight.
+    ///
+    /// This measures the distance of every pair of points once, so it takes quadratic time (about 50 million
+    /// distances for 10,000 points) but only linear memory.
+    #[wasm_bindgen(js_name = "minimum_spanning_tree")]
+    pub fn minimum_spanning_tree_js(
+        &self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<SpanningTree, JsValue> {
+        self.minimum_spanning_tree(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns the number of input points of the most recent clustering, including points left out by sampling,
+    /// or 0 if there is none.
+    pub fn point_count(&self) -> usize {
+        self.last_run.as_ref().map_or(0, LastRun::point_count)
+    }
+
+    /// Returns a Float32Array with the centroid (mean position) of each cluster of the most recent clustering, with
+    /// as many scalars per cluster as the clustered points have dimensions. The noise group has no centroid, so
+    /// centroid `i` belongs to the `i`-th cluster of the packed result.
+    #[wasm_bindgen(js_name = "centroids")]
+    pub fn centroids_js(&self) -> Result<Vec<f32>, JsValue> {
+        self.centroids()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Float32Array with one value per dimension of the clustered points that measures how much the
+    /// dimension separates the clusters of the most recent clustering: the share of the members' variance along it
+    /// that lies between clusters rather than within them. Values range from 0 (the clusters overlap completely
+    /// along this axis) to 1 (members differ only between clusters), e.g. to decide which axes to keep.
+    ///
+    /// Noise is ignored, points in several clusters count once per cluster, and constant dimensions get 0. With a
+    /// random projection, the dimensions are those of the projected points.
+    #[wasm_bindgen(js_name = "dimension_separation")]
+    pub fn dimension_separation_js(&self) -> Result<Vec<f32>, JsValue> {
+        self.dimension_separation()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns a Float32Array of `clusterCount * clusterCount` distances between the centroids (see `centroids`)
+    /// of the clusters of the most recent clustering, excluding the noise group, measured with the metric of that
+    /// clustering. Entry `i * clusterCount + j` is the distance between clusters `i` and `j`, e.g. to lay out
+    /// clusters in an overview or connect nearby ones. A single cluster gives a 1×1 matrix holding 0.
+    #[wasm_bindgen(js_name = "centroid_distances")]
+    pub fn centroid_distances_js(&self) -> Result<Vec<f32>, JsValue> {
+        self.centroid_distances()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Sets the clusters of a previous frame, so that `cluster_ids` keeps the ids of clusters that stay in roughly
+    /// the same region. `centroids` are packed like the result of `centroids`, and `ids` holds one id per centroid,
+    /// e.g. the previous result of `cluster_ids`.
+    ///
+    /// Only centroids within `max_distance` of each other are matched.
+    ///
+    /// Fails if `centroids` can't hold one centroid per id, i.e. if their length is not a multiple of the number of
+    /// ids. `cluster_ids` fails if the centroids have a different number of dimensions than the clustered points.
+    pub fn set_previous_clusters(
+        &mut self,
+        centroids: Vec<f32>,
+        ids: Vec<u32>,
+        max_distance: f64,
+    ) -> Result<(), JsValue> {
+        let whole = match ids.len() {
+            0 => centroids.is_empty(),
+            count => centroids.len().is_multiple_of(count),
+        };
+        if !whole {
+            return Err(JsValue::from(format!(
+                "{}",
+                ClusterError::PointVectorLengthMismatch {
+                    param_name: "centroids",
+                    expected: ids.len(),
+                    got: centroids.len(),
+                }
+            )));
+        }
+        self.previous = Some(PreviousClusters {
+            centroids,
+            ids,
+            max_distance,
+        });
+        Ok(())
+    }
+
+    /// Forgets the previous clusters, so `cluster_ids` numbers clusters in order again.
+    pub fn clear_previous_clusters(&mut self) {
+        self.previous = None;
+    }
+
+    /// Chooses how `cluster_ids` matches clusters to the previous clusters.
+    ///
+    /// By default the matching is greedy: the closest pair of centroids is matched first, then the closest of the
+    /// remaining ones, and so on. That is fast, but when clusters swap neighbors in a busy transition, a close pair
+    /// can take a previous cluster that another cluster needed more. The optimal matching (Hungarian algorithm)
+    /// instead finds the most pairs of centroids within `max_distance` and, among those matchings, the one with the
+    /// smallest sum of centroid distances, in time cubic in the number of clusters.
+    pub fn set_optimal_cluster_matching(&mut self, optimal: bool) {
+        self.optimal_matching = optimal;
+    }
+
+    /// Returns a Uint32Array with a stable id for each cluster of the most recent clustering (excluding the noise
+    /// group, so id `i` belongs to the `i`-th cluster of the packed result), e.g. to keep colors consistent across
+    /// the frames of a transition.
+    ///
+    /// Without previous clusters, the ids are `0, 1, 2, …`. Otherwise clusters and previous clusters are matched
+    /// greedily, closest centroids first (or optimally, see `set_optimal_cluster_matching`). Matched clusters take
+    /// over the previous id, and the others get fresh ids above all previous ones.
+    #[wasm_bindgen(js_name = "cluster_ids")]
+    pub fn cluster_ids_js(&self) -> Result<Vec<u32>, JsValue> {
+        self.cluster_ids()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Returns, for every input point of the most recent clustering in input order, the id of the cluster it
+    /// belongs to most (as returned by `cluster_ids`) and its membership there, e.g. to color points by cluster
+    /// and fade them by membership.
+    ///
+    /// Memberships are soft labels, so core points with a density of at least `pts_max` have membership 1. Points
+    /// in several clusters get the one with the highest label, ties going to the earlier cluster (or a random one
+    /// after `set_random_tie_break`). Noise points (and points left out by sampling) have id -1 and membership 0.
+    #[wasm_bindgen(js_name = "flat_labels")]
+    pub fn flat_labels_js(&self) -> Result<FlatLabels, JsValue> {
+        self.flat_labels()
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters data once for each eps in `schedule`, e.g. to let users drill down into coarser or finer clusters.
+    /// Levels are sorted by increasing eps, which becomes `eps_max` while `eps_min` keeps its configured ratio
+    /// to `eps_max`; the other parameters and options stay as configured, except for `set_max_clusters`.
+    ///
+    /// As eps grows, clusters merge. The result numbers the clusters (without noise) of all levels consecutively
+    /// and links each one to its parent on the next level: the cluster that most of its members belong to there.
+    /// Records warnings like a clustering, but keeps the most recent clustering.
+    #[wasm_bindgen(js_name = "cluster_hierarchy")]
+    pub fn cluster_hierarchy_js(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        schedule: Vec<f64>,
+    ) -> Result<ClusterHierarchy, JsValue> {
+        self.cluster_hierarchy(packed_data, dimensions, schedule)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters data once for every eps in `eps_levels` (`eps_max`, with `eps_min` scaled to keep its ratio to
+    /// `eps_max`), e.g. fine and coarse clusterings for zoom levels of a level-of-detail view. Returns one result
+    /// per level in the given order, each in the format of `cluster`.
+    ///
+    /// This is faster than clustering once per level: neighborhoods are searched only once with the largest eps,
+    /// and the tighter levels only check which of those neighbors are close enough. That costs memory for the
+    /// neighbors of all points while clustering, so with a memory budget (see `set_memory_budget`) each level
+    /// searches its neighborhoods on its own instead, with the same result. Like `cluster_hierarchy`, this ignores
+    /// the maximum cluster count and doesn't change `last_result`.
+    #[wasm_bindgen(js_name = "cluster_multilevel")]
+    pub fn cluster_multilevel_js(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+        eps_levels: Vec<f64>,
+    ) -> Result<ClusterLevels, JsValue> {
+        self.cluster_multilevel(packed_data, dimensions, eps_levels)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Clusters data once with every metric added with `add_ensemble_metric` (or only the configured metric if
+    /// none were added) and returns the consensus: clusters of the points that are in the same cluster under every
+    /// metric, e.g. to only show clusters that don't depend on the choice of distance.
+    ///
+    /// A point belongs to the cluster where its label is highest. Points that are noise under any metric are
+    /// consensus noise. Consensus members are core points if they are core points under every metric, with their
+    /// lowest label. The agreement score of a consensus member is the mean size of its consensus cluster relative
+    /// to its cluster under each metric (1 if all metrics found the same cluster); that of a noise point is the
+    /// fraction of metrics under which it is noise. Like `cluster_hierarchy`, this ignores the maximum cluster
+    /// count and doesn't change `last_result`.
+    #[wasm_bindgen(js_name = "cluster_ensemble")]
+    pub fn cluster_ensemble_js(
+        &mut self,
+        packed_data: &[u8],
+        dimensions: usize,
+    ) -> Result<EnsembleClusters, JsValue> {
+        self.cluster_ensemble(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+
+    /// Like `cluster`, but also returns details explaining the soft labels.
+    #[wasm_bindgen(js_name = "cluster_detailed")]
+    pub fn cluster_detailed_js(
+        &mut self,
+        packed_data: Vec<u8>,
+        dimensions: usize,
+    ) -> Result<ClusterResult, JsValue> {
+        self.cluster_detailed(packed_data, dimensions)
+            .map_err(|err| JsValue::from(format!("{}", err)))
+    }
+}
+
+/// Generates packed Float32Array data of `cluster_count` Gaussian blobs for benchmarks, see
+/// [`crate::bench::generate_clusters`]. Only available when built with the `bench` feature
+/// (`wasm-pack build -- --features bench`).
+#[cfg(feature = "bench")]
+#[wasm_bindgen]
+pub fn generate_clusters(
+    point_count: usize,
+    dimensions: usize,
+    cluster_count: usize,
+    spread: f64,
+    seed: u32,
+) -> Vec<u8> {
+    crate::bench::generate_clusters(point_count, dimensions, cluster_count, spread, seed as u64)
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Result of [`FuzzyCluster::cluster_detailed_js`].
+#[wasm_bindgen]
+pub struct ClusterResult {
+    packed: Vec<u8>,
+    border_distances: Vec<f32>,
+    border_cores: Vec<i32>,
+}
+
+#[wasm_bindgen]
+impl ClusterResult {
+    /// Packed clusters in the same format that `cluster` returns.
+    #[wasm_bindgen(getter)]
+    pub fn packed(&self) -> Vec<u8> {
+        self.packed.clone()
+    }
+
+    /// Float32Array with one entry per packed assignment: for border points, the distance to the core point that
+    /// determined the soft label; NaN for core and noise points.
+    #[wasm_bindgen(getter)]
+    pub fn border_distances(&self) -> Vec<f32> {
+        self.border_distances.clone()
+    }
+
+    /// Int32Array with one entry per packed assignment: for border (and reassigned) points, the index of the core
+    /// point that determined the soft label (the one `border_distances` measures to), e.g. to draw a line from each
+    /// border point to its core point; -1 for core and noise points.
+    #[wasm_bindgen(getter)]
+    pub fn border_cores(&self) -> Vec<i32> {
+        self.border_cores.clone()
+    }
+}
+
+/// Clusters in compressed sparse row layout, see [`FuzzyCluster::cluster_csr_js`].
+///
+/// The members of cluster `i` are `indices[offsets[i]..offsets[i + 1]]` with soft labels at the same positions in
+/// `labels`, so `offsets` has one more entry than there are clusters and ends with the total assignment count.
+/// Clusters appear in the same order as in the packed format; if `has_noise` is set, the last one holds the noise.
+#[wasm_bindgen]
+pub struct CsrClusters {
+    offsets: Vec<u32>,
+    indices: Vec<u32>,
+    ids: Option<Vec<i64>>,
+    categories: Vec<u8>,
+    labels: Vec<f32>,
+    has_noise: bool,
+}
+
+#[wasm_bindgen]
+impl CsrClusters {
+    /// Uint32Array of offsets into `indices`, `categories` and `labels` where each cluster starts, plus the total
+    /// length.
+    #[wasm_bindgen(getter)]
+    pub fn offsets(&self) -> Vec<u32> {
+        self.offsets.clone()
+    }
+
+    /// Uint32Array of the original point indices of all clusters' members.
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    /// BigInt64Array of the external ids of the members parallel to `indices`, or `undefined` if no ids were set
+    /// with `set_point_ids` or `set_point_ids_bigint`.
+    #[wasm_bindgen(getter)]
+    pub fn ids(&self) -> Option<Vec<i64>> {
+        self.ids.clone()
+    }
+
+    /// Uint8Array of the categories of the members parallel to `indices`, with the codes of the packed format: 0 for
+    /// core, 1 for border, 2 for noise and 3 for reassigned points.
+    #[wasm_bindgen(getter)]
+    pub fn categories(&self) -> Vec<u8> {
+        self.categories.clone()
+    }
+
+    /// Float32Array of the soft labels parallel to `indices`.
+    #[wasm_bindgen(getter)]
+    pub fn labels(&self) -> Vec<f32> {
+        self.labels.clone()
+    }
+
+    /// Whether the last cluster is the group of noise points.
+    #[wasm_bindgen(getter)]
+    pub fn has_noise(&self) -> bool {
+        self.has_noise
+    }
+}
+
+/// The most common attribute value of each cluster, see [`FuzzyCluster::dominant_attributes_js`].
+#[wasm_bindgen]
+pub struct DominantAttributes {
+    values: Vec<u32>,
+    purities: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl DominantAttributes {
+    /// Uint32Array with the most common attribute value of each cluster.
+    #[wasm_bindgen(getter)]
+    pub fn values(&self) -> Vec<u32> {
+        self.values.clone()
+    }
+
+    /// Float32Array with the fraction of each cluster's members that have its most common value, from 0 to 1.
+    #[wasm_bindgen(getter)]
+    pub fn purities(&self) -> Vec<f32> {
+        self.purities.clone()
+    }
+}
+
+/// Progress of a clustering session, see [`FuzzyCluster::step_js`].
+#[wasm_bindgen]
+pub struct StepResult {
+    done: bool,
+    progress: f64,
+}
+
+#[wasm_bindgen]
+impl StepResult {
+    /// Whether the session is done and its result is the most recent clustering.
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    /// Fraction of points visited so far, from 0 to 1.
+    #[wasm_bindgen(getter)]
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+}
+
+/// Cluster counts of an eps sweep, see [`FuzzyCluster::sweep_eps_js`].
+#[wasm_bindgen]
+pub struct EpsSweep {
+    cluster_counts: Vec<u32>,
+    noise_fractions: Vec<f32>,
+    join_eps: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl EpsSweep {
+    /// Uint32Array with the number of clusters (without noise) for each eps.
+    #[wasm_bindgen(getter)]
+    pub fn cluster_counts(&self) -> Vec<u32> {
+        self.cluster_counts.clone()
+    }
+
+    /// Float32Array with the fraction of points that are noise for each eps.
+    #[wasm_bindgen(getter)]
+    pub fn noise_fractions(&self) -> Vec<f32> {
+        self.noise_fractions.clone()
+    }
+
+    /// Float32Array with, for each point, the smallest of the eps values at which it belongs to a cluster, i.e. the
+    /// eps at which it stops being noise, or NaN if it is noise at every eps. Small values mark dense regions,
+    /// e.g. for a color encoding of density reachability.
+    #[wasm_bindgen(getter)]
+    pub fn join_eps(&self) -> Vec<f32> {
+        self.join_eps.clone()
+    }
+}
+
+/// Consensus of clusterings with several metrics, see [`FuzzyCluster::cluster_ensemble_js`].
+#[wasm_bindgen]
+pub struct EnsembleClusters {
+    packed: Vec<u8>,
+    agreement: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl EnsembleClusters {
+    /// The consensus clusters in the format of `cluster`.
+    #[wasm_bindgen(getter)]
+    pub fn packed(&self) -> Vec<u8> {
+        self.packed.clone()
+    }
+
+    /// Float32Array with the agreement score of each input point, from 0 to 1.
+    #[wasm_bindgen(getter)]
+    pub fn agreement(&self) -> Vec<f32> {
+        self.agreement.clone()
+    }
+}
+
+/// Edges of a minimum spanning tree, see [`FuzzyCluster::minimum_spanning_tree_js`].
+#[wasm_bindgen]
+pub struct SpanningTree {
+    endpoints: Vec<u32>,
+    weights: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl SpanningTree {
+    /// Uint32Array with the two point indices of each edge, so edge `k` connects `endpoints[2k]` and
+    /// `endpoints[2k + 1]`.
+    #[wasm_bindgen(getter)]
+    pub fn endpoints(&self) -> Vec<u32> {
+        self.endpoints.clone()
+    }
+
+    /// Float32Array with the weight (distance between the endpoints) of each edge, in increasing order.
+    #[wasm_bindgen(getter)]
+    pub fn weights(&self) -> Vec<f32> {
+        self.weights.clone()
+    }
+}
+
+/// Groups of point indices in compressed sparse row layout, see [`FuzzyCluster::find_duplicates_js`] and
+/// [`FuzzyCluster::border_points_js`].
+///
+/// The members of group `i` are `indices[offsets[i]..offsets[i + 1]]`.
+#[wasm_bindgen]
+pub struct IndexGroups {
+    offsets: Vec<u32>,
+    indices: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl IndexGroups {
+    /// Number of groups.
+    #[wasm_bindgen(getter)]
+    pub fn group_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Uint32Array of offsets into `indices` where each group starts, plus the total length.
+    #[wasm_bindgen(getter)]
+    pub fn offsets(&self) -> Vec<u32> {
+        self.offsets.clone()
+    }
+
+    /// Uint32Array of the point indices of all groups' members, in input order within each group.
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+}
+
+/// Clusters of a density sweep and how they merge, see [`FuzzyCluster::cluster_hierarchy_js`].
+///
+/// Clusters of all levels are numbered consecutively without noise: the clusters of level `l` are
+/// `level_offsets[l]..level_offsets[l + 1]`, in the order of that level's packed result.
+#[wasm_bindgen]
+pub struct ClusterHierarchy {
+    eps: Vec<f64>,
+    level_offsets: Vec<u32>,
+    parents: Vec<i32>,
+    sizes: Vec<u32>,
+    packed: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl ClusterHierarchy {
+    /// Number of levels, one per eps of the schedule.
+    #[wasm_bindgen(getter)]
+    pub fn level_count(&self) -> usize {
+        self.eps.len()
+    }
+
+    /// Float64Array with the eps (`eps_max`) of each level, in increasing order.
+    #[wasm_bindgen(getter)]
+    pub fn eps(&self) -> Vec<f64> {
+        self.eps.clone()
+    }
+
+    /// Uint32Array with the number of the first cluster of each level, followed by the total cluster count.
+    #[wasm_bindgen(getter)]
+    pub fn level_offsets(&self) -> Vec<u32> {
+        self.level_offsets.clone()
+    }
+
+    /// Int32Array with the number of each cluster's parent on the next level, or -1 on the last level and for
+    /// clusters whose members are all noise on the next level.
+    #[wasm_bindgen(getter)]
+    pub fn parents(&self) -> Vec<i32> {
+        self.parents.clone()
+    }
+
+    /// Uint32Array with the number of assignments of each cluster.
+    #[wasm_bindgen(getter)]
+    pub fn sizes(&self) -> Vec<u32> {
+        self.sizes.clone()
+    }
+
+    /// Returns the clusters of `level` in the format of `cluster`, or an empty array for a level that doesn't
+    /// exist.
+    pub fn packed(&self, level: usize) -> Vec<u8> {
+        self.packed.get(level).cloned().unwrap_or_default()
+    }
+}
+
+/// Clusterings at several eps levels, see [`FuzzyCluster::cluster_multilevel_js`].
+#[wasm_bindgen]
+pub struct ClusterLevels {
+    eps: Vec<f64>,
+    packed: Vec<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl ClusterLevels {
+    /// Number of levels, one per requested eps.
+    #[wasm_bindgen(getter)]
+    pub fn level_count(&self) -> usize {
+        self.eps.len()
+    }
+
+    /// Float64Array with the eps (`eps_max`) of each level, in the requested order.
+    #[wasm_bindgen(getter)]
+    pub fn eps(&self) -> Vec<f64> {
+        self.eps.clone()
+    }
+
+    /// Returns the clusters of `level` in the format of `cluster`, or an empty array for a level that doesn't
+    /// exist.
+    pub fn packed(&self, level: usize) -> Vec<u8> {
+        self.packed.get(level).cloned().unwrap_or_default()
+    }
+}
+
+/// Cluster id and membership of each point, see [`FuzzyCluster::flat_labels_js`].
+#[wasm_bindgen]
+pub struct FlatLabels {
+    ids: Vec<i32>,
+    memberships: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl FlatLabels {
+    /// Int32Array with each point's cluster id, or -1 for noise.
+    #[wasm_bindgen(getter)]
+    pub fn ids(&self) -> Vec<i32> {
+        self.ids.clone()
+    }
+
+    /// Float32Array with each point's membership in the cluster of `ids`, or 0 for noise.
+    #[wasm_bindgen(getter)]
+    pub fn memberships(&self) -> Vec<f32> {
+        self.memberships.clone()
+    }
+}
+
+/// Spread of each cluster, see [`FuzzyCluster::cluster_spread_js`].
+#[wasm_bindgen]
+pub struct ClusterSpread {
+    diameters: Vec<f32>,
+    mean_centroid_distances: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl ClusterSpread {
+    /// Float32Array with each cluster's largest distance between two members.
+    #[wasm_bindgen(getter)]
+    pub fn diameters(&self) -> Vec<f32> {
+        self.diameters.clone()
+    }
+
+    /// Float32Array with each cluster's mean distance of the members to their centroid (mean position).
+    #[wasm_bindgen(getter)]
+    pub fn mean_centroid_distances(&self) -> Vec<f32> {
+        self.mean_centroid_distances.clone()
+    }
+}
+
+/// Clusters and warnings of [`FuzzyCluster::recluster_with_js`].
+#[wasm_bindgen]
+pub struct ReclusterResult {
+    packed: Vec<u8>,
+    warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl ReclusterResult {
+    /// Packed clusters in the same format that `cluster` returns.
+    #[wasm_bindgen(getter)]
+    pub fn packed(&self) -> Vec<u8> {
+        self.packed.clone()
+    }
+
+    /// Array of strings describing non-fatal problems, like `last_run_warnings` after a clustering.
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> js_sys::Array {
+        self.warnings.iter().map(|w| JsValue::from_str(w)).collect()
+    }
+}
@@ -0,0 +1,94 @@
+//! How clustering parameters and options apply to the clustered points.
+#![cfg(feature = "wasm")]
+
+use d3st_wasm::FuzzyCluster;
+
+/// 50 points on a line, 0.1 apart.
+fn line() -> Vec<u8> {
+    (0..50)
+        .flat_map(|step| (step as f32 * 0.1).to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn pts_max_above_the_point_count_only_caps_labels() {
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 2.0, 100.0);
+    clustering.cluster_js(line(), 1).unwrap();
+    let labels = clustering.flat_labels_js().unwrap();
+    assert!(labels.ids().iter().all(|&id| id == 0));
+    assert!(labels.memberships().iter().all(|&label| label < 1.0));
+}
+
+#[test]
+fn pts_min_is_bounded_by_the_total_weight() {
+    let clusters = |clustering: &FuzzyCluster| {
+        let ids = clustering.flat_labels_js().unwrap().ids();
+        ids.iter().filter(|&&id| id >= 0).count()
+    };
+    // heavy points can exceed a pts_min of the point count
+    let mut clustering = FuzzyCluster::new(5.0, 5.0, 50.0, 100.0);
+    clustering.set_point_weights(vec![2.0; 50]);
+    clustering.cluster_js(line(), 1).unwrap();
+    assert_eq!(clusters(&clustering), 50);
+    // a sample of 25 points can't, which tolerant mode fixes
+    let mut clustering = FuzzyCluster::new(5.0, 5.0, 25.0, 100.0);
+    clustering.set_sample_every(2);
+    clustering.set_tolerant(true);
+    clustering.cluster_js(line(), 1).unwrap();
+    assert_eq!(clusters(&clustering), 25);
+}
+
+#[test]
+fn sweeping_to_an_infinite_eps_matches_counting() {
+    let mut clustering = FuzzyCluster::new(0.0, 0.05, 2.0, 4.0);
+    clustering.cluster_js(line(), 1).unwrap();
+    let eps = [0.05, 0.15, f64::INFINITY];
+    let sweep = clustering.sweep_eps_js(eps.to_vec(), 2.0, 4.0).unwrap();
+    let counts: Vec<u32> = eps
+        .iter()
+        .map(|&eps| clustering.count_clusters_js(eps, 2.0, 4.0).unwrap() as u32)
+        .collect();
+    assert_eq!(sweep.cluster_counts(), counts);
+    assert_eq!(counts[2], 1);
+}
+
+#[test]
+fn multilevel_clustering_within_a_memory_budget_matches_the_shared_search() {
+    let eps = vec![0.15, 0.25, 1.0];
+    let mut shared = FuzzyCluster::new(0.1, 0.15, 2.0, 4.0);
+    let expected = shared
+        .cluster_multilevel_js(&line(), 1, eps.clone())
+        .unwrap();
+    let mut budgeted = FuzzyCluster::new(0.1, 0.15, 2.0, 4.0);
+    budgeted.set_memory_budget(1 << 20);
+    let levels = budgeted.cluster_multilevel_js(&line(), 1, eps).unwrap();
+    for level in 0..3 {
+        assert_eq!(levels.packed(level), expected.packed(level));
+    }
+}
+
+#[test]
+fn density_weighted_labels_count_only_the_sample() {
+    let mut sampled = FuzzyCluster::new(0.2, 0.45, 3.0, 5.0);
+    sampled.set_density_weighted_labels(true);
+    sampled.set_sample_every(2);
+    sampled.cluster_js(line(), 1).unwrap();
+    let every_other: Vec<u8> = (0..25)
+        .flat_map(|step| (step as f32 * 0.2).to_le_bytes())
+        .collect();
+    let mut subset = FuzzyCluster::new(0.2, 0.45, 3.0, 5.0);
+    subset.set_density_weighted_labels(true);
+    subset.cluster_js(every_other, 1).unwrap();
+    let sampled_labels: Vec<f32> = sampled
+        .flat_labels_js()
+        .unwrap()
+        .memberships()
+        .into_iter()
+        .step_by(2)
+        .collect();
+    let subset_labels = subset.flat_labels_js().unwrap().memberships();
+    assert_eq!(sampled_labels, subset_labels);
+    assert!(subset_labels
+        .iter()
+        .any(|&label| label > 0.0 && label < 1.0));
+}
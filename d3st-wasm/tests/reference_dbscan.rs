@@ -0,0 +1,245 @@
+//! Compares crisp clusterings (`eps_min = eps_max`, `pts_min = pts_max`) with a textbook DBSCAN on small fixtures
+//! with known labels.
+//!
+//! Crisp FuzzyDBSCAN agrees with DBSCAN (e.g. scikit-learn's with `min_samples = pts`) on core points and noise.
+//! Border points intentionally differ: DBSCAN gives a border point to the first cluster that reaches it, while
+//! FuzzyDBSCAN lists it in every cluster with a core point within eps.
+
+use std::collections::BTreeSet;
+
+use d3st_wasm::fuzzy_dbscan::{Category, Cluster, FuzzyDBSCAN, MetricSpace};
+
+struct Point(f64, f64);
+
+impl MetricSpace for Point {
+    fn distance(&self, other: &Self) -> f64 {
+        ((other.0 - self.0).powi(2) + (other.1 - self.1).powi(2)).sqrt()
+    }
+}
+
+/// Two interleaved half circles with two outliers, as `(x, y, label)` with label -1 for noise.
+const MOONS: [(f64, f64, i32); 34] = [
+    (0.98, 0.0, 0),
+    (0.97, 0.21, 0),
+    (0.92, 0.38, 0),
+    (0.78, 0.61, 0),
+    (0.65, 0.73, 0),
+    (0.53, 0.86, 0),
+    (0.33, 0.95, 0),
+    (0.11, 0.97, 0),
+    (-0.1, 1.02, 0),
+    (-0.31, 0.97, 0),
+    (-0.49, 0.84, 0),
+    (-0.65, 0.75, 0),
+    (-0.82, 0.56, 0),
+    (-0.89, 0.41, 0),
+    (-0.97, 0.23, 0),
+    (-0.99, 0.03, 0),
+    (-0.01, 0.52, 1),
+    (0.02, 0.32, 1),
+    (0.11, 0.07, 1),
+    (0.17, -0.1, 1),
+    (0.36, -0.25, 1),
+    (0.51, -0.38, 1),
+    (0.69, -0.46, 1),
+    (0.89, -0.49, 1),
+    (1.11, -0.47, 1),
+    (1.32, -0.43, 1),
+    (1.52, -0.34, 1),
+    (1.68, -0.26, 1),
+    (1.83, -0.06, 1),
+    (1.94, 0.1, 1),
+    (1.99, 0.27, 1),
+    (2.02, 0.5, 1),
+    (2.6, 1.2, -1),
+    (-1.2, -0.9, -1),
+];
+
+/// Three Gaussian blobs with two outliers, as `(x, y, label)` with label -1 for noise.
+const BLOBS: [(f64, f64, i32); 26] = [
+    (-0.24, -0.23, 0),
+    (0.13, -0.46, 0),
+    (-0.03, -0.45, 0),
+    (0.22, 0.04, 0),
+    (0.27, -0.1, 0),
+    (0.08, -0.06, 0),
+    (-0.15, 0.03, 0),
+    (-0.25, -0.07, 0),
+    (3.15, 0.01, 1),
+    (2.92, 0.44, 1),
+    (3.01, -0.12, 1),
+    (3.03, -0.1, 1),
+    (2.92, -0.07, 1),
+    (3.41, 0.0, 1),
+    (3.04, 0.13, 1),
+    (3.4, -0.04, 1),
+    (-0.12, 3.49, 2),
+    (-0.29, 2.93, 2),
+    (0.13, 3.46, 2),
+    (-0.19, 2.51, 2),
+    (0.13, 2.9, 2),
+    (-0.08, 3.09, 2),
+    (0.04, 3.06, 2),
+    (-0.09, 3.26, 2),
+    (1.5, 1.5, -1),
+    (4.2, 3.1, -1),
+];
+
+fn points(fixture: &[(f64, f64, i32)]) -> Vec<Point> {
+    fixture.iter().map(|&(x, y, _)| Point(x, y)).collect()
+}
+
+fn crisp(eps: f64, pts: f64) -> FuzzyDBSCAN {
+    FuzzyDBSCAN {
+        eps_min: eps,
+        eps_max: eps,
+        pts_min: pts,
+        pts_max: pts,
+    }
+}
+
+/// Textbook DBSCAN: a point is a core point if at least `min_points` points (including itself) are within `eps`.
+/// Returns a cluster number per point, or -1 for noise, and whether each point is a core point.
+fn reference_dbscan(points: &[Point], eps: f64, min_points: usize) -> (Vec<i32>, Vec<bool>) {
+    let neighbors: Vec<Vec<usize>> = points
+        .iter()
+        .map(|p| {
+            (0..points.len())
+                .filter(|&q| p.distance(&points[q]) <= eps)
+                .collect()
+        })
+        .collect();
+    let core: Vec<bool> = neighbors.iter().map(|n| n.len() >= min_points).collect();
+    let mut labels = vec![-1; points.len()];
+    let mut next_label = 0;
+    for start in 0..points.len() {
+        if labels[start] != -1 || !core[start] {
+            continue;
+        }
+        labels[start] = next_label;
+        let mut stack = vec![start];
+        while let Some(p) = stack.pop() {
+            for &q in &neighbors[p] {
+                if labels[q] == -1 {
+                    labels[q] = next_label;
+                    if core[q] {
+                        stack.push(q);
+                    }
+                }
+            }
+        }
+        next_label += 1;
+    }
+    (labels, core)
+}
+
+/// Groups the points with `labels[i] == label` for each label except -1, as a set of sets.
+fn partition(labels: &[i32], include: impl Fn(usize) -> bool) -> BTreeSet<BTreeSet<usize>> {
+    let mut groups = std::collections::BTreeMap::<i32, BTreeSet<usize>>::new();
+    for (index, &label) in labels.iter().enumerate() {
+        if label >= 0 && include(index) {
+            groups.entry(label).or_default().insert(index);
+        }
+    }
+    groups.into_values().collect()
+}
+
+fn members(cluster: &Cluster, category: Category) -> BTreeSet<usize> {
+    cluster
+        .iter()
+        .filter(|assignment| assignment.category == category)
+        .map(|assignment| assignment.index)
+        .collect()
+}
+
+/// Checks a crisp clustering of `fixture` against the reference DBSCAN and the fixture's labels.
+fn assert_matches_reference(fixture: &[(f64, f64, i32)], eps: f64, min_points: usize) {
+    let points = points(fixture);
+    let clusters = crisp(eps, min_points as f64).cluster(&points);
+    let (reference, core) = reference_dbscan(&points, eps, min_points);
+
+    for cluster in &clusters {
+        let indices: Vec<usize> = cluster.iter().map(|assignment| assignment.index).collect();
+        let unique: BTreeSet<usize> = indices.iter().copied().collect();
+        assert_eq!(unique.len(), indices.len(), "a point is listed twice");
+    }
+    let (noise, real) = clusters.split_last().expect("noise group");
+    assert!(noise.iter().all(|a| a.category == Category::Noise));
+
+    // core points split into the same clusters
+    let cores: BTreeSet<BTreeSet<usize>> =
+        real.iter().map(|c| members(c, Category::Core)).collect();
+    assert_eq!(cores, partition(&reference, |index| core[index]));
+
+    // every point the reference calls noise is noise, and nothing else
+    let reference_noise: BTreeSet<usize> =
+        (0..points.len()).filter(|&i| reference[i] == -1).collect();
+    assert_eq!(members(noise, Category::Noise), reference_noise);
+
+    // border points are in every cluster with a core point within eps, which includes the reference's choice
+    for cluster in real {
+        let cluster_cores = members(cluster, Category::Core);
+        for border in members(cluster, Category::Border) {
+            assert!(!core[border]);
+            assert!(cluster_cores
+                .iter()
+                .any(|&c| points[c].distance(&points[border]) <= eps));
+        }
+    }
+    for (index, &label) in reference.iter().enumerate() {
+        if label >= 0 {
+            let reference_cores: BTreeSet<usize> = (0..points.len())
+                .filter(|&i| core[i] && reference[i] == label)
+                .collect();
+            let cluster = real
+                .iter()
+                .find(|c| members(c, Category::Core) == reference_cores)
+                .expect("matching cluster");
+            assert!(cluster.iter().any(|a| a.index == index));
+        }
+    }
+
+    // and the partition is the one the fixture was generated with
+    let labels: Vec<i32> = fixture.iter().map(|&(_, _, label)| label).collect();
+    assert_eq!(
+        partition(&reference, |_| true),
+        partition(&labels, |_| true)
+    );
+}
+
+#[test]
+fn two_moons_match_reference() {
+    assert_matches_reference(&MOONS, 0.3, 3);
+}
+
+#[test]
+fn blobs_match_reference() {
+    assert_matches_reference(&BLOBS, 0.5, 4);
+}
+
+#[test]
+fn crisp_labels_are_binary() {
+    let clusters = crisp(0.3, 3.).cluster(&points(&MOONS));
+    // border points are within eps of a core point, which gives full membership without a fuzzy ramp
+    assert!(clusters.iter().flatten().all(|a| a.label == 1.0));
+}
+
+#[test]
+fn fuzzy_borders_have_soft_labels() {
+    // with a fuzzy ramp, points far from dense regions have labels strictly between 0 and 1
+    let dbscan = FuzzyDBSCAN {
+        eps_min: 0.2,
+        eps_max: 0.6,
+        pts_min: 2.,
+        pts_max: 5.,
+    };
+    let clusters = dbscan.cluster(&points(&BLOBS));
+    let labels: Vec<f64> = clusters
+        .iter()
+        .flatten()
+        .filter(|a| a.category != Category::Noise)
+        .map(|a| a.label)
+        .collect();
+    assert!(labels.iter().all(|&label| (0.0..=1.0).contains(&label)));
+    assert!(labels.iter().any(|&label| label > 0.0 && label < 1.0));
+}
@@ -0,0 +1,113 @@
+//! Pins down the reproducibility contract: every seeded option produces byte-identical results for the same seed,
+//! also across separate instances (whose hash maps are seeded differently), and different seeds can produce
+//! different results.
+#![cfg(feature = "wasm")]
+
+use d3st_wasm::FuzzyCluster;
+
+/// Two vertical lines of points, and a line of sparser points exactly halfway between them that belong to both
+/// with the same membership.
+fn fixture() -> Vec<f32> {
+    let mut points = Vec::new();
+    for step in 0..80 {
+        let y = step as f32 * 0.25;
+        points.extend([0.0, y, 1.75, y]);
+    }
+    for step in 0..10 {
+        points.extend([0.875, step as f32 * 2.0]);
+    }
+    points
+}
+
+fn bytes(points: &[f32]) -> Vec<u8> {
+    points
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+fn clustering() -> FuzzyCluster {
+    FuzzyCluster::new(0.5, 1.0, 3.0, 6.0)
+}
+
+/// Clusters the fixture with a fresh instance configured by `configure` and returns the packed result.
+fn packed(configure: impl Fn(&mut FuzzyCluster)) -> Vec<u8> {
+    let mut clustering = clustering();
+    configure(&mut clustering);
+    clustering.cluster_js(bytes(&fixture()), 2).unwrap()
+}
+
+/// Checks that `result` is the same for the same seed and differs between the two seeds.
+fn assert_seeded<T: PartialEq + std::fmt::Debug>(result: impl Fn(u32) -> T, seeds: [u32; 2]) {
+    assert_eq!(result(seeds[0]), result(seeds[0]));
+    assert_eq!(result(seeds[1]), result(seeds[1]));
+    assert_ne!(result(seeds[0]), result(seeds[1]));
+}
+
+#[test]
+fn random_samples_are_reproducible() {
+    assert_seeded(
+        |seed| packed(|clustering| clustering.set_sample_random(100, seed)),
+        [1, 2],
+    );
+}
+
+#[test]
+fn random_projections_are_reproducible() {
+    assert_seeded(
+        |seed| packed(|clustering| clustering.set_random_projection(2, seed)),
+        [1, 2],
+    );
+}
+
+#[test]
+fn random_tie_breaks_are_reproducible() {
+    let labels = |configure: &dyn Fn(&mut FuzzyCluster)| {
+        let mut clustering = clustering();
+        configure(&mut clustering);
+        clustering.cluster_js(bytes(&fixture()), 2).unwrap();
+        let labels = clustering.flat_labels_js().unwrap();
+        (labels.ids(), labels.memberships())
+    };
+    assert_seeded(
+        |seed| labels(&|clustering| clustering.set_random_tie_break(seed)),
+        [1, 2],
+    );
+    // without a seed, every tie goes to the earlier cluster
+    let (ids, _) = labels(&|_| {});
+    assert!(ids[160..].iter().all(|&id| id == ids[0]));
+}
+
+#[test]
+fn assignment_stability_is_reproducible() {
+    let mut clustering = clustering();
+    clustering.cluster_js(bytes(&fixture()), 2).unwrap();
+    let stability = |seed| clustering.assignment_stability_js(0.05, 5, seed).unwrap();
+    assert_seeded(stability, [1, 2]);
+    // the lines are dense enough to survive the jitter
+    assert!(stability(1)[..160]
+        .iter()
+        .all(|&stability| stability == 1.0));
+}
+
+#[test]
+fn unseeded_options_are_deterministic() {
+    // these options go through hash maps internally, which are seeded per instance
+    let configure = |clustering: &mut FuzzyCluster| {
+        clustering.set_exclusion_radius(0.1);
+        clustering.set_density_weighted_labels(true);
+        clustering.set_max_clusters(1);
+    };
+    assert_eq!(packed(configure), packed(configure));
+}
+
+#[cfg(feature = "bench")]
+#[test]
+fn synthetic_data_is_reproducible() {
+    use d3st_wasm::bench::generate_clusters;
+
+    assert_seeded(
+        |seed| generate_clusters(200, 3, 4, 0.05, seed as u64),
+        [1, 2],
+    );
+}
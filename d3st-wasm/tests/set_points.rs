@@ -0,0 +1,95 @@
+//! Clusterings of points set once with `set_points`.
+#![cfg(feature = "wasm")]
+
+use std::sync::Arc;
+use std::thread;
+
+use d3st_wasm::FuzzyCluster;
+
+/// Two vertical lines of points.
+fn lines() -> Vec<u8> {
+    (0..80)
+        .flat_map(|step| {
+            let y = step as f32 * 0.25;
+            [0.0, y, 1.75, y]
+        })
+        .flat_map(|value: f32| value.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn reclustering_set_points_matches_clustering() {
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    clustering.set_points_js(&lines(), 2).unwrap();
+    let mut expected = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    assert_eq!(
+        clustering.recluster_js().unwrap(),
+        expected.cluster_js(lines(), 2).unwrap()
+    );
+    // the index of the set points stays valid across parameter changes
+    clustering.set_point_counts(2, 4);
+    expected.set_point_counts(2, 4);
+    assert_eq!(
+        clustering.recluster_js().unwrap(),
+        expected.cluster_js(lines(), 2).unwrap()
+    );
+    assert_eq!(clustering.neighbors_of_js(1, 0.3).unwrap(), [3]);
+    assert_eq!(
+        clustering.neighbors_of_js(0, 1.75).unwrap(),
+        [1, 2, 4, 6, 8, 10, 12, 14]
+    );
+}
+
+#[test]
+fn reclustering_after_update_points_clusters_the_moved_points() {
+    let moved: Vec<u8> = [50.0f32, 50.0]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    clustering.set_points_js(&lines(), 2).unwrap();
+    clustering.recluster_js().unwrap();
+    assert!(!clustering
+        .update_points_js(vec![0], &moved, 2)
+        .unwrap()
+        .is_empty());
+    let updated = clustering.last_result_js().unwrap();
+    assert_eq!(clustering.recluster_js().unwrap(), updated);
+    let mut data = lines();
+    data[..8].copy_from_slice(&moved);
+    let mut expected = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    assert_eq!(updated, expected.cluster_js(data, 2).unwrap());
+    assert_eq!(
+        clustering.neighbors_of_js(0, 0.3).unwrap(),
+        Vec::<u32>::new()
+    );
+    assert_eq!(clustering.neighbors_of_js(2, 0.3).unwrap(), [4]);
+}
+
+#[test]
+fn shared_reclusterings_leave_the_instance_unchanged() {
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    clustering.set_points_js(&lines(), 2).unwrap();
+    let clustering = Arc::new(clustering);
+    let parameters = [(0.5, 1.0, 3.0, 6.0), (2.0, 2.0, 1.0, 1.0)];
+    let results: Vec<_> = parameters
+        .map(|(eps_min, eps_max, pts_min, pts_max)| {
+            let clustering = Arc::clone(&clustering);
+            thread::spawn(move || {
+                clustering
+                    .recluster_with_js(eps_min, eps_max, pts_min, pts_max)
+                    .unwrap()
+                    .packed()
+            })
+        })
+        .into_iter()
+        .map(|worker| worker.join().unwrap())
+        .collect();
+    for ((eps_min, eps_max, pts_min, pts_max), packed) in parameters.into_iter().zip(results) {
+        let mut expected = FuzzyCluster::new(eps_min, eps_max, pts_min, pts_max);
+        expected.set_points_js(&lines(), 2).unwrap();
+        assert_eq!(packed, expected.recluster_js().unwrap());
+    }
+    // neither result became the most recent clustering
+    assert_eq!(clustering.point_count(), 0);
+}
@@ -0,0 +1,66 @@
+//! Incremental updates of the most recent clustering after points moved.
+#![cfg(feature = "wasm")]
+
+use d3st_wasm::FuzzyCluster;
+
+/// A dense line of points and a sparse one next to it, which only a second pass with a larger eps clusters.
+fn points() -> Vec<f32> {
+    let mut points = Vec::new();
+    for step in 0..40 {
+        points.extend([0.0, step as f32 * 0.25]);
+    }
+    for step in 0..10 {
+        points.extend([5.0, step as f32 * 2.0]);
+    }
+    points
+}
+
+fn bytes(points: &[f32]) -> Vec<u8> {
+    points
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn updates_after_a_noise_pass_match_clustering_without_it() {
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    let first_pass = clustering.cluster_js(bytes(&points()), 2).unwrap();
+    let second_pass = clustering.recluster_noise_js(2.5, 3.0).unwrap();
+    assert_ne!(second_pass, first_pass);
+
+    let moved = [0.1f32, 0.0];
+    let changed = clustering
+        .update_points_js(vec![0], &bytes(&moved), 2)
+        .unwrap();
+    let mut data = points();
+    data[..2].copy_from_slice(&moved);
+    let mut expected = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    assert_eq!(
+        clustering.last_result_js().unwrap(),
+        expected.cluster_js(bytes(&data), 2).unwrap()
+    );
+    // the points of the discarded pass are noise again
+    assert_eq!(changed, (40..50).collect::<Vec<u32>>());
+}
+
+#[test]
+fn repeated_updates_match_clustering() {
+    let mut clustering = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+    let mut data = points();
+    clustering.cluster_js(bytes(&data), 2).unwrap();
+    // enough moves that the spatial index kept across updates is rebuilt on the way
+    for step in 0..12 {
+        let index = step * 3 % 40;
+        let moved = [0.5 + step as f32 * 0.1, data[index * 2 + 1]];
+        clustering
+            .update_points_js(vec![index as u32], &bytes(&moved), 2)
+            .unwrap();
+        data[index * 2..index * 2 + 2].copy_from_slice(&moved);
+        let mut expected = FuzzyCluster::new(0.5, 1.0, 3.0, 6.0);
+        assert_eq!(
+            clustering.last_result_js().unwrap(),
+            expected.cluster_js(bytes(&data), 2).unwrap()
+        );
+    }
+}